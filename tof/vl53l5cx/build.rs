@@ -88,7 +88,7 @@ fn main() -> Result<()> {
             .expect("OUT_DIR to have a value");
 
         fs::write(TMP, out_dir)
-            .expect(format!("Unable to write {TMP}").as_str());
+            .unwrap_or_else(|_| panic!("Unable to write {TMP}"));
     }
 
     //---
@@ -98,9 +98,9 @@ fn main() -> Result<()> {
         process_pins(toml, &board_id)?;
     }
 
-    // Link arguments
+    // Link arguments (only for the MCU; host side tests link as usual)
     //
-    {
+    if matches!(env::var("CARGO_CFG_TARGET_OS").as_deref(), core::result::Result::Ok("none")) {     // not anyhow's 'Ok'
         let link_args: Vec<&str> = vec!(
             "-Tlinkall.x",
             "-Tdefmt.x"     // required by 'defmt'
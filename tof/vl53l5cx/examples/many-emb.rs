@@ -28,7 +28,6 @@ use esp_hal::{
     i2c::I2c,
     peripherals::I2C0,
     prelude::*,
-    time::{now, Instant, Duration},
     timer::timg::TimerGroup,
     Blocking
 };
@@ -36,6 +35,8 @@ use esp_hal::{
 #[cfg(feature = "examples_serial")]
 use esp_println::println;
 
+use embassy_time::{Duration, Instant};
+
 use static_cell::StaticCell;
 
 extern crate vl53l5cx;
@@ -180,12 +181,12 @@ async fn defmt_print_results(mut rcv: DynReceiver<'static, FRes>) {
     let mut t0: Option<Instant> = None;
 
     loop {
-        let FlockResults{board_index, res, temp_degc, time_stamp} = rcv.changed().await;
+        let FlockResults{board_index, res, temp_degc, time_stamp, ..} = rcv.changed().await;
 
         let dt: Duration = time_stamp - *(t0.get_or_insert(time_stamp));
-        let sign = if dt.is_zero() {""} else {"+"};
+        let sign = if dt.as_ticks() == 0 {""} else {"+"};
 
-        info!("Data #{}: ({}, {}{}ms)", board_index, temp_degc, sign, dt.to_millis());
+        info!("Data #{}: ({}, {}{}ms)", board_index, temp_degc, sign, dt.as_millis());
 
         info!(".target_status:    {}", res.target_status);
         #[cfg(any(feature = "targets_per_zone_2", feature = "targets_per_zone_3", feature = "targets_per_zone_4"))]
@@ -203,6 +204,8 @@ async fn defmt_print_results(mut rcv: DynReceiver<'static, FRes>) {
         info!(".distance_mm:      {}", res.distance_mm);
         #[cfg(feature = "reflectance_percent")]
         info!(".reflectance:      {}", res.reflectance);
+        #[cfg(feature = "motion_indicator")]
+        info!(".motion:           {}", res.motion);
     }
 }
 
@@ -231,13 +234,13 @@ struct Timings {
 
 impl Timings {
     fn new() -> Self {
-        Self{ t0: now(), t1: Self::DUMMY, t2: Self::DUMMY }
+        Self{ t0: Instant::now(), t1: Self::DUMMY, t2: Self::DUMMY }
     }
 
-    fn results(&mut self) { self.t1 = now(); }
+    fn results(&mut self) { self.t1 = Instant::now(); }
 
     fn results_passed(mut self) -> Self {
-        self.t2 = now(); self
+        self.t2 = Instant::now(); self
     }
 
     fn report(/*move*/ self) {
@@ -246,7 +249,7 @@ impl Timings {
         let dt2 = self.t2 - self.t1;
 
         fn ms(dur: /*&*/Duration) -> f32 {
-            dur.to_micros() as f32 / 1000.0
+            dur.as_micros() as f32 / 1000.0
         }
 
         debug!("Timing [ms] (total {=f32}): wait+read {}, passing {}", ms(dt_total), ms(dt1), ms(dt2));
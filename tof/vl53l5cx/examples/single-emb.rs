@@ -22,11 +22,12 @@ use esp_hal::{
     i2c::I2c,
     peripherals::I2C0,
     prelude::*,
-    time::{now, Instant, Duration},
     timer::timg::TimerGroup,
     Blocking
 };
 
+use embassy_time::{Duration, Instant};
+
 use static_cell::StaticCell;
 
 extern crate vl53l5cx;
@@ -107,14 +108,16 @@ async fn ranging(/*move*/ vl: VL, pinINT: Input<'static>) {
 
     let mut ring = vl.start_ranging(&c, pinINT).unwrap();
 
-    let t0 = now();
+    let t0 = Instant::now();
     let mut _t = Timings::new();
+    let mut buf = SoloResults::empty();     // reused over the rounds
 
     for _round in 0..10 {
         _t.t0();
 
-        let SoloResults{res, temp_degc, time_stamp} = ring.get_data() .await
+        ring.get_data_into(&mut buf) .await
             .unwrap();
+        let SoloResults{res, temp_degc, time_stamp, ..} = &buf;
         if _round==0 { info!("Skipping first results (normally not valid)");
             continue;
         }
@@ -123,7 +126,7 @@ async fn ranging(/*move*/ vl: VL, pinINT: Input<'static>) {
 
         // tbd. Consider making output a separate task (feed via a channel)
         {
-            info!("Data ({}, {})", temp_degc, (time_stamp-t0).to_millis());
+            info!("Data ({}, {})", temp_degc, (*time_stamp-t0).as_millis());
 
             info!(".target_status:    {}", res.target_status);
             #[cfg(any(feature = "targets_per_zone_2", feature = "targets_per_zone_3", feature = "targets_per_zone_4"))]
@@ -141,6 +144,8 @@ async fn ranging(/*move*/ vl: VL, pinINT: Input<'static>) {
             info!(".distance_mm:      {}", res.distance_mm);
             #[cfg(feature = "reflectance_percent")]
             info!(".reflectance:      {}", res.reflectance);
+            #[cfg(feature = "motion_indicator")]
+            info!(".motion:           {}", res.motion);
         }
         _t.results_passed();
         _t.report();
@@ -160,13 +165,13 @@ impl Timings {
     }
 
     fn t0(&mut self) {
-        self.t0 = now();
+        self.t0 = Instant::now();
     }
     fn results(&mut self) {
-        self.t1 = now();
+        self.t1 = Instant::now();
     }
     fn results_passed(&mut self) {
-        self.t2 = now();
+        self.t2 = Instant::now();
     }
 
     fn report(&mut self) {
@@ -175,7 +180,7 @@ impl Timings {
         let dt2 = self.t2 - self.t1;
 
         fn ms(dur: /*&*/Duration) -> f32 {
-            dur.to_micros() as f32 / 1000.0
+            dur.as_micros() as f32 / 1000.0
         }
 
         debug!("Timing [ms] (total {=f32}): wait+read {}, passing {}", ms(dt_total), ms(dt1), ms(dt2));
@@ -0,0 +1,63 @@
+/*
+* How the I2C bus is shared between the sensors, and other devices (drivers) on the same bus.
+*
+* The bus is only held for the duration of a single I2C transaction (one chunk; see
+* 'uld_platform.rs'). The delays between the transactions are spent outside of it. Thus, other
+* drivers get their transfers done in between ours, even during the long firmware upload.
+*
+*   - 'RefCell<I2C>': Embassy tasks on the same priority. Another user borrowing the bus *across
+*       an '.await'* makes our access panic; so does an interrupt (executor) of a higher priority
+*       using the bus while we have it.
+*
+*   - 'embassy_sync::blocking_mutex::Mutex<M, RefCell<I2C>>' (with the 'shared_bus' feature):
+*       the same mutex that 'embassy-embedded-hal's 'shared_bus::blocking::i2c::I2cDevice' uses,
+*       so give that to the other drivers. With 'CriticalSectionRawMutex', the access is safe
+*       also between interrupt priorities.
+*
+*   - 'critical_section::Mutex<RefCell<I2C>>' (with the 'shared_bus' feature): what
+*       'embedded-hal-bus's 'i2c::CriticalSectionDevice' uses.
+*
+* Note: A critical section keeps interrupts off while we're on the bus: up to ~6ms per transaction
+*       (256 bytes at 400kHz).
+*/
+use core::cell::RefCell;
+
+use embedded_hal::i2c::I2c;
+
+#[cfg(feature = "shared_bus")]
+use embassy_sync::blocking_mutex::{raw::RawMutex, Mutex};
+
+pub trait I2cBus {
+    type I2C: I2c;
+
+    /*
+    * Run 'f' with exclusive access to the bus.
+    */
+    fn with_bus<R>(&self, f: impl FnOnce(&mut Self::I2C) -> R) -> R;
+}
+
+impl<I2C: I2c> I2cBus for RefCell<I2C> {
+    type I2C = I2C;
+
+    fn with_bus<R>(&self, f: impl FnOnce(&mut I2C) -> R) -> R {
+        f(&mut self.borrow_mut())
+    }
+}
+
+#[cfg(feature = "shared_bus")]
+impl<M: RawMutex, I2C: I2c> I2cBus for Mutex<M, RefCell<I2C>> {
+    type I2C = I2C;
+
+    fn with_bus<R>(&self, f: impl FnOnce(&mut I2C) -> R) -> R {
+        self.lock(|bus| f(&mut bus.borrow_mut()))
+    }
+}
+
+#[cfg(feature = "shared_bus")]
+impl<I2C: I2c> I2cBus for critical_section::Mutex<RefCell<I2C>> {
+    type I2C = I2C;
+
+    fn with_bus<R>(&self, f: impl FnOnce(&mut I2C) -> R) -> R {
+        critical_section::with(|cs| f(&mut self.borrow_ref_mut(cs)))
+    }
+}
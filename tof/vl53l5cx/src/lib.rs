@@ -9,34 +9,79 @@ extern crate alloc;
 mod ranging;
 #[cfg(feature = "flock")]
 mod ranging_flock;
+#[cfg(feature = "flock")]
+mod ranging_flock_dyn;
+#[cfg(feature = "supervised")]
+mod supervised;
 
+#[cfg(feature = "persist")]
+mod persist;
+mod i2c_bus;
 mod uld_platform;
 mod vl;
+#[cfg(any(feature = "single", feature = "flock"))]
+mod wake;
 
 #[cfg(feature = "single")]
 pub use ranging::{SoloResults, Ranging};
+#[cfg(any(feature = "single", feature = "flock"))]
+pub use wake::NoInt;
+#[cfg(feature = "supervised")]
+pub use supervised::{Event, Recovery, Stall, Supervised};
 
 #[cfg(feature = "flock")]
 pub use {
     ranging_flock::{FlockResults, RangingFlock},
-    vl::VLsExt      // tbd. how to provide such methods properly?  Compare with 'fugit'.
+    ranging_flock_dyn::{FlockResultsDyn, RangingFlockDyn},
+    vl::{VLsExt, VLsExtDyn}     // tbd. how to provide such methods properly?  Compare with 'fugit'.
 };
 
 pub use vl::{
     VL,
 };
+pub use i2c_bus::I2cBus;
 
 // Elements we pass through from the ULD level. Careful here: ideally all API is under our direct control!
 pub use vl53l5cx_uld::{
     API_REVISION as ULD_VERSION,
+    CalibrationBlob,
+    CalibrationError,
+    Confidence,
+    ConfigError,
     DEFAULT_I2C_ADDR,
+    Error,
+    FrameStats,
     I2cAddr,
     Mode,
+    Orientation,
     RangingConfig,
-    Result as UldResult,
+    RangingConfigDyn,
+    Resolution,
+    Result,
     ResultsData,    // leaked (intentionally) via '{Flock|Solo}Results'
+    ResultsDataDyn, // -''- via 'FlockResultsDyn'
+    Target,         // via 'ResultsData::zones()'
     TargetOrder,
+    TargetStatus,
+    Targets,
+    Zone,
+    Zones,
     units,
 };
 
-pub type Instant = esp_hal::time::Instant;
+#[cfg(feature = "distance_mm")]
+pub use vl53l5cx_uld::{Point, PointCloud};
+#[cfg(feature = "motion_indicator")]
+pub use vl53l5cx_uld::MotionConfig;
+#[cfg(feature = "detection_thresholds")]
+pub use vl53l5cx_uld::{Combine, Measurement, ThresholdsConfig, Window};
+#[cfg(feature = "xtalk")]
+pub use vl53l5cx_uld::{XtalkData, XTALK_SIZE};
+#[cfg(feature = "persist")]
+pub use {
+    persist::Persist,
+    vl53l5cx_uld::{BoardConfig, ConfigRecord, PersistError},
+};
+
+// Time stamps are from 'embassy-time'; the HAL (e.g. 'esp-hal-embassy') provides its driver.
+pub use embassy_time::{Duration, Instant};
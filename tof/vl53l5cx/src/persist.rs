@@ -0,0 +1,53 @@
+/*
+* Keeping the boards' setup (addresses, ranging configs, calibration) in the MCU's flash.
+*
+* The record format, and its checks, are in 'vl53l5cx_uld' ('ConfigRecord'); this only keeps the
+* flash (any 'embedded-storage' 'NorFlash'; on the ESP32, 'esp_storage::FlashStorage') and where
+* in it the record goes. The application reserves the region: 'ConfigRecord::<N>::MAX_SIZE' bytes, rounded up to
+* 4kB (the flash's erase size), at a 4kB boundary. E.g. the 'nvs' partition (0x9000..0xf000) of the
+* default partition table, if nothing else uses it.
+*/
+#![cfg(feature = "persist")]
+
+#[cfg(feature = "defmt")]
+use defmt::warn;
+
+use embedded_storage::nor_flash::NorFlash;
+
+use vl53l5cx_uld::{
+    ConfigRecord,
+    PersistError,
+};
+
+pub struct Persist<F> {
+    flash: F,
+    at: u32,
+}
+
+impl<F: NorFlash> Persist<F> {
+    /*
+    * 'at': flash offset of the reserved region
+    */
+    pub fn new(flash: F, at: u32) -> Self {
+        Self{ flash, at }
+    }
+
+    /*
+    * The stored record, or 'f()' if there's none (first boot) or it cannot be used.
+    */
+    pub fn load_or_else<const N: usize>(&mut self, f: impl FnOnce() -> ConfigRecord<N>) -> ConfigRecord<N> {
+        ConfigRecord::load_or_else(&mut self.flash, self.at, |_e| {
+            #[cfg(feature = "defmt")]
+            warn!("No stored setup ({}); using defaults", _e);
+            f()
+        })
+    }
+
+    pub fn load<const N: usize>(&mut self) -> Result<ConfigRecord<N>, PersistError> {
+        ConfigRecord::load(&mut self.flash, self.at)
+    }
+
+    pub fn store<const N: usize>(&mut self, rec: &ConfigRecord<N>) -> Result<(), PersistError> {
+        rec.store(&mut self.flash, self.at)
+    }
+}
@@ -4,14 +4,15 @@
 #![cfg(feature = "single")]
 
 #[cfg(feature = "defmt")]
-use defmt::{trace};
+use defmt::{trace, warn};
 
-use esp_hal::{
-    gpio::Input,
-    time::{Instant, now}
-};
+use embassy_time::{with_timeout, Duration, Instant, Timer};
+
+use embedded_hal_async::digital::Wait;
 
 use vl53l5cx_uld::{
+    Error,
+    FrameStats,
     RangingConfig,
     Result,
     ResultsData,
@@ -20,6 +21,7 @@ use vl53l5cx_uld::{
 };
 
 use crate::{
+    wake::{NoInt, Wake},
     VL,
 };
 
@@ -28,57 +30,163 @@ pub struct SoloResults<const DIM: usize>{
     pub res: ResultsData<DIM>,
     pub temp_degc: TempC,
     pub time_stamp: Instant,
+    pub streamcount: u8,    // sensor's frame counter (0..=254; wraps)
+}
+
+impl<const DIM: usize> SoloResults<DIM> {
+    /*
+    * A buffer for '.get_data_into()'; keep it over the frames.
+    */
+    pub fn empty() -> Self {
+        Self{ res: ResultsData::empty(), temp_degc: TempC(0), time_stamp: Instant::from_ticks(0), streamcount: 0 }
+    }
 }
 
 /*
 * Ranging for a single board.
 */
 #[cfg(feature = "single")]
-pub struct Ranging<const DIM: usize> {    // DIM: 4|8
+pub struct Ranging<const DIM: usize, INT = NoInt> {    // DIM: 4|8
     uld: State_Ranging<DIM>,
-    pinINT: Input<'static>
+    wake: Wake<INT>,
+    unread: bool,       // a frame was seen ready, but reading it failed
 }
 
 #[cfg(feature = "single")]
-impl<const DIM: usize> Ranging<DIM> {
-    pub(crate) fn start(vl: VL, cfg: &RangingConfig<DIM>, pinINT: Input<'static>) -> Result<Ranging<DIM>> {
+impl<const DIM: usize, INT: Wait> Ranging<DIM, INT> {
+    pub(crate) fn start(vl: VL, cfg: &RangingConfig<DIM>, wake: Wake<INT>) -> Result<Self> {
         let uld = vl.into_uld().start_ranging(cfg)?;
-        Ok(Self{ uld, pinINT })
+        Ok(Self{ uld, wake, unread: false })
+    }
+
+    #[cfg(feature = "supervised")]
+    pub(crate) fn from_parts(uld: State_Ranging<DIM>, wake: Wake<INT>) -> Self {
+        Self{ uld, wake, unread: false }
     }
 
     pub async fn get_data(&mut self) -> Result<SoloResults<DIM>> {
-        let t0 = now();
-
-        // Two kinds of spec can be implemented here:
-        //  - wait for the NEXT INT edge, then provide results
-        //  - provide results; if none, wait for the INT edge
-        //
-        // 1st:
-        //      Always provides fresh results.
-        //      Can miss a result, if it's gotten ready faster than the app moved from 'start()'
-        //      to here (unlikely).
-        //
-        // 2nd:
-        //      Would provide more results (theoretically), but some may be stale
-        //
-        // Since we time the results, 1st feels more.. better choice. For now, at least.
-        //
-        self.pinINT.wait_for_falling_edge() .await;
-        let ts = now();     // nearest time after the (presumed) scan
-
-        trace!("Received falling edge of INT, after {}", now() - t0);
-
-        match self.uld.is_ready()? {
-            true => (),
-            false => panic!("INT edge seen but sensor has no data"),
+        let mut out = SoloResults::empty();
+        self.get_data_into(&mut out).await?;
+        Ok(out)
+    }
+
+    /*
+    * Like '.get_data()', but gives up after 'timeout' ('Error::NoDataInTime').
+    */
+    pub async fn get_data_with_timeout(&mut self, timeout: Duration) -> Result<SoloResults<DIM>> {
+        let mut out = SoloResults::empty();
+        match with_timeout(timeout, self.get_data_into(&mut out)).await {
+            Ok(r) => r.map(|_| out),
+            Err(_) => Err(Error::NoDataInTime),
+        }
+    }
+
+    /*
+    * Like '.get_data()', but fills a buffer the caller keeps. Avoids moving the (up to kilobytes
+    * of) results around, for each frame.
+    *
+    * An INT edge without new data (a glitch on the line?) is skipped; we wait for the next one.
+    * Use '.get_data_with_timeout()' (or 'Supervised') to not wait forever, if the sensor stalls.
+    */
+    pub async fn get_data_into(&mut self, out: &mut SoloResults<DIM>) -> Result<()> {
+        while !self.wait_and_read(out).await? {
+            #[cfg(feature = "defmt")]
+            warn!("INT edge seen but sensor has no data; waiting for the next");
         }
+        Ok(())
+    }
+
+    /*
+    * Wait for the next frame, and read it. 'false' if INT came, but the sensor had no new data.
+    */
+    pub(crate) async fn wait_and_read(&mut self, out: &mut SoloResults<DIM>) -> Result<bool> {
+        #[cfg(feature = "defmt")]
+        let t0 = Instant::now();
+
+        match self.wake {
+            // Two kinds of spec can be implemented here:
+            //  - wait for the NEXT INT edge, then provide results
+            //  - provide results; if none, wait for the INT edge
+            //
+            // 1st:
+            //      Always provides fresh results.
+            //      Can miss a result, if it's gotten ready faster than the app moved from 'start()'
+            //      to here (unlikely). Such misses show in '.frame_stats()'.
+            //
+            // 2nd:
+            //      Would provide more results (theoretically), but some may be stale
+            //
+            // Since we time the results, 1st feels more.. better choice. For now, at least.
+            //
+            // Note: With detection thresholds ('RangingConfig::with_thresholds()'), the sensor only
+            //      pulls INT down for frames where a threshold tripped. We thus sleep over the others.
+            //
+            Wake::Int(ref mut pin) => {
+                pin.wait_for_falling_edge() .await
+                    .map_err(|_| Error::Pin)?;
+                let ts = Instant::now();     // nearest time after the (presumed) scan
+
+                #[cfg(feature = "defmt")]
+                trace!("Received falling edge of INT, after {}", ts - t0);
+                self.read_into(out, ts)
+            },
+
+            // Without INT, we can only ask. A frame that's ready is new (not seen by '.is_ready()'
+            // before), so this is the 2nd spec. The time stamp is up to one interval late.
+            //
+            // Note: Detection thresholds don't filter here. The sensor makes (and tells of) every
+            //      frame; the thresholds only gate its INT pin (UM2884 > "Programmable thresholds").
+            //      Polled, all frames are thus given.
+            //
+            Wake::Poll(interval) => loop {
+                if self.read_into(out, Instant::now())? {
+                    #[cfg(feature = "defmt")]
+                    trace!("Polled data, after {}", Instant::now() - t0);
+                    return Ok(true);
+                }
+                Timer::after(interval).await;
+            },
+        }
+    }
+
+    /*
+    * Read the results, if the sensor has new ones ('false' if not).
+    *
+    * A frame that was seen ready, but failed to read, is read on the next call. '.is_ready()'
+    * would not tell of it again. If the sensor has made a newer one by then, that one is read.
+    */
+    pub(crate) fn read_into(&mut self, out: &mut SoloResults<DIM>, ts: Instant) -> Result<bool> {
+        if !self.unread {
+            if !self.uld.is_ready()? {
+                return Ok(false);
+            }
+            self.unread = true;
+        }
+        out.temp_degc = self.uld.get_data_into(&mut out.res)?;
+        self.unread = false;
+        out.time_stamp = ts;
+        out.streamcount = self.uld.streamcount();
+        Ok(true)
+    }
 
-        let (res, temp_degc) = self.uld.get_data()?;
-        Ok( SoloResults{ res, temp_degc, time_stamp: ts } )
+    /*
+    * Frames read, missed, read twice or stale, since the start; see 'vl53l5cx_uld'.
+    */
+    pub fn frame_stats(&self) -> &FrameStats {
+        self.uld.frame_stats()
     }
 
     pub fn stop(self) -> Result<VL> {
         let uld = self.uld.stop()?;
         Ok(VL::recreate(uld))
     }
+
+    /*
+    * Leave the sensor as it is (see 'State_Ranging::abandon()'); only the 'INT' pin (if any) comes back.
+    */
+    #[cfg(feature = "supervised")]
+    pub(crate) fn abandon(self) -> Wake<INT> {
+        self.uld.abandon();
+        self.wake
+    }
 }
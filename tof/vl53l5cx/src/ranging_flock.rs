@@ -6,22 +6,26 @@
 #[cfg(feature = "defmt")]
 use defmt::{debug,trace};
 
-use esp_hal::{
-    gpio::Input,
-    time::{now, Instant}
-};
+use embassy_time::{with_timeout, Duration, Instant, Timer};
+
+use embedded_hal::digital::InputPin;
+use embedded_hal_async::digital::Wait;
 
 use vl53l5cx_uld::{
     units::TempC,
+    Error,
+    FrameStats,
     RangingConfig,
     Result,
     ResultsData,
     State_Ranging,
+    State_Ranging_Dyn,
 };
 
 use arrayvec::ArrayVec;
 
 use crate::{
+    wake::{NoInt, Wake},
     VL
 };
 
@@ -31,6 +35,16 @@ pub struct FlockResults<const DIM: usize>{
     pub res: ResultsData<DIM>,
     pub temp_degc: TempC,
     pub time_stamp: Instant,
+    pub streamcount: u8,    // the board's frame counter (0..=254; wraps)
+}
+
+impl<const DIM: usize> FlockResults<DIM> {
+    /*
+    * A buffer for '.get_data_into()'; keep it over the frames.
+    */
+    pub fn empty() -> Self {
+        Self{ board_index: 0, res: ResultsData::empty(), temp_degc: TempC(0), time_stamp: Instant::from_ticks(0), streamcount: 0 }
+    }
 }
 
 /*
@@ -38,24 +52,30 @@ pub struct FlockResults<const DIM: usize>{
 *
 * Note: A generator would be ideal for this (could keep the state within it).
 */
-pub struct RangingFlock<const N: usize, const DIM: usize> {
+pub struct RangingFlock<const N: usize, const DIM: usize, INT = NoInt> {
     ulds: [State_Ranging<DIM>;N],
-    pinINT: Input<'static>,
-    pending: ArrayVec<FlockResults<DIM>,N>    // tbd. pick suitable capacity once we know the behaviour
+    sched: Scheduler<N,INT>,
 }
 
-impl<const N: usize, const DIM: usize> RangingFlock<N,DIM> {
+impl<const N: usize, const DIM: usize, INT: InputPin + Wait> RangingFlock<N,DIM,INT> {
 
-    pub(crate) fn start(vls: [VL;N], cfg: &RangingConfig<DIM>, pinINT: Input<'static>) -> Result<Self> {
+    /*
+    * 'cfg(i)' is the config of board 'i'.
+    */
+    pub(crate) fn start<'c>(vls: [VL;N], cfg: impl Fn(usize) -> &'c RangingConfig<DIM>, wake: Wake<INT>) -> Result<Self> {
 
-        // Turn the ULD level handles into "ranging" state, and start tracking the 'pinINT'.
+        // Turn the ULD level handles into "ranging" state, and start tracking the 'INT' (or polling).
 
-        let ulds: [State_Ranging<DIM>;N] = array_try_map(vls, |x| x.into_uld().start_ranging(cfg))?;
+        let mut i = 0;
+        let ulds: [State_Ranging<DIM>;N] = array_try_map(vls, |x| {
+            let r = x.into_uld().start_ranging(cfg(i));
+            i += 1;
+            r
+        })?;
 
         Ok(Self{
             ulds,
-            pinINT,
-            pending: ArrayVec::new()
+            sched: Scheduler::new(wake),
         })
     }
 
@@ -66,8 +86,98 @@ impl<const N: usize, const DIM: usize> RangingFlock<N,DIM> {
     *       makes it easier for the recipient, compared to getting 1..N results, at once.
     */
     pub async fn get_data(&mut self) -> Result<FlockResults<DIM>> {
+        let mut out = FlockResults::empty();
+        self.get_data_into(&mut out).await?;
+        Ok(out)
+    }
+
+    /*
+    * Like '.get_data()', but gives up after 'timeout' ('Error::NoDataInTime').
+    */
+    pub async fn get_data_with_timeout(&mut self, timeout: Duration) -> Result<FlockResults<DIM>> {
+        let mut out = FlockResults::empty();
+        match with_timeout(timeout, self.get_data_into(&mut out)).await {
+            Ok(r) => r.map(|_| out),
+            Err(_) => Err(Error::NoDataInTime),
+        }
+    }
 
-        // Time stamp the results as fast after knowing they exist, as possible.
+    /*
+    * Like '.get_data()', but fills a buffer the caller keeps.
+    *
+    * Note: Results are read from a board only when handed out. Until then, we only keep the
+    *       board's index; no copies of the results. The time stamp is taken at the read, so it
+    *       matches the frame that was read (a board kept pending may have made a newer one).
+    */
+    pub async fn get_data_into(&mut self, out: &mut FlockResults<DIM>) -> Result<()> {
+        let i = self.sched.next(&mut self.ulds).await?;
+        let time_stamp = Instant::now();
+
+        out.temp_degc = self.ulds[i].get_data_into(&mut out.res)?;
+        out.board_index = i;
+        out.time_stamp = time_stamp;
+        out.streamcount = self.ulds[i].streamcount();
+        Ok(())
+    }
+
+    /*
+    * Frames read, missed, read twice or stale, for board 'i'. A stale read is one where the board
+    * replaced the frame while pending; the older frame is then lost (and counted as a gap).
+    */
+    pub fn frame_stats(&self, i: usize) -> &FrameStats {
+        self.ulds[i].frame_stats()
+    }
+
+    /*
+    * Stop the boards. The 'INT' pin is given back, unless polling.
+    */
+    pub fn stop(self) -> Result<([VL;N], Option<INT>)> {
+        let vls = array_try_map(self.ulds, |x| {
+            let uld = x.stop()?;
+            Ok( VL::recreate(uld) )
+        })?;
+
+        Ok( (vls, self.sched.into_pin()) )
+    }
+}
+
+/*
+* A board's ranging state, as far as scheduling is concerned. Boards of a fixed ('State_Ranging<DIM>')
+* and runtime ('State_Ranging_Dyn') resolution are scheduled alike.
+*/
+pub(crate) trait Board {
+    fn is_ready(&mut self) -> Result<bool>;
+}
+
+impl<const DIM: usize> Board for State_Ranging<DIM> {
+    fn is_ready(&mut self) -> Result<bool> { State_Ranging::is_ready(self) }
+}
+
+impl Board for State_Ranging_Dyn {
+    fn is_ready(&mut self) -> Result<bool> { State_Ranging_Dyn::is_ready(self) }
+}
+
+/*
+* Which board to read next: the boards seen ready (in that order), and how to sleep until there
+* are some.
+*/
+pub(crate) struct Scheduler<const N: usize, INT> {
+    wake: Wake<INT>,
+    pending: ArrayVec<usize,N>      // boards with data ready; first seen, first given
+}
+
+impl<const N: usize, INT: InputPin + Wait> Scheduler<N,INT> {
+    pub(crate) fn new(wake: Wake<INT>) -> Self {
+        Self{ wake, pending: ArrayVec::new() }
+    }
+
+    /*
+    * The next board to read. The caller reads (and time stamps) its results right away.
+    */
+    pub(crate) async fn next<B: Board>(&mut self, boards: &mut [B;N]) -> Result<usize> {
+
+        // Hand out the boards in the order they were seen ready; the one seen first has waited
+        // the longest.
 
         // 1. Anything in the 'pending'? Give them first.
         // 2. Check for new results
@@ -77,39 +187,47 @@ impl<const N: usize, const DIM: usize> RangingFlock<N,DIM> {
         //
         // Note: All of this logic is experimental. Let's trace what happens in reality, and
         //      adjust!
-        //      - some measurements may get lost, but the number should be minimal
+        //      - some measurements may get lost, but the number should be minimal ('.frame_stats()'
+        //        tells)
         //      - if two measurements from the same board, the older one shall never replace the newer one
         //        (they can be both delivered)
         //      - time stamps should be as close to actual measurement as possible!
 
         // Trace if we see new data
-        #[cfg(all())]
+        //
+        // Note: Disabled. '.is_ready()' only tells of a frame once; asking here left the main loop
+        //      below without it (the frame was lost, until the next INT).
+        //
+        #[cfg(not(all()))]
         {
-            for (i,uld) in self.ulds.iter_mut().enumerate() {
-                if uld.is_ready()? {
+            for (i,b) in boards.iter_mut().enumerate() {
+                if b.is_ready()? {
                     trace!("Data available on entry: {}", i);
                 }
             }
         }
 
         loop {
-            // Add new results to the 'self.pending'.
-            for (i,uld) in self.ulds.iter_mut().enumerate() /*.rev()*/ {
-                if uld.is_ready()? {
-                    let time_stamp = now();
-                    let (res,temp_degc) = uld.get_data()?;
-                    let o = FlockResults{ board_index: i, res, temp_degc, time_stamp };
+            // Add boards with new results to the 'self.pending'.
+            //
+            // Note: A board already pending isn't asked again; '.is_ready()' would not see its
+            //      (unread) frame as new.
+            for (i,b) in boards.iter_mut().enumerate() /*.rev()*/ {
+                if self.pending.contains(&i) { continue }
 
+                if b.is_ready()? {
+                    #[cfg(feature = "defmt")]
                     debug!("New data from #{}, pending becomes {}", i, self.pending.len()+1);
-                    self.pending.push(o);
+                    self.pending.push(i);
                 } else {
+                    #[cfg(feature = "defmt")]
                     debug!("No new data from #{}", i);
                 }
             }
 
-            // Return already pending results, one at a time.
-            if let Some(tmp) = self.pending.pop() {
-                return Ok(tmp);
+            // Return already pending results, one at a time; oldest first.
+            if let Some(i) = self.pending.pop_at(0) {
+                return Ok(i);
             }
 
             // No data; sleep until either edge
@@ -120,25 +238,31 @@ impl<const N: usize, const DIM: usize> RangingFlock<N,DIM> {
             //      ends (100us from the last new result), it's possible there's yet more data we
             //      didn't hear of. Checking both edges ensures we get even those, with sub-ms delay.
             //
+            // Without INT, sleep for the poll interval. Time stamps are then up to that late.
+            //
             assert!(self.pending.is_empty());
-            {
-                trace!("Going to sleep (INT {}).", if self.pinINT.is_low() {"still low"} else {"high"});
+            match self.wake {
+                Wake::Int(ref mut pinINT) => {
+                    #[cfg(feature = "defmt")]
+                    trace!("Going to sleep (INT {}).", if matches!(pinINT.is_low(), Ok(true)) {"still low"} else {"high"});
 
-                let t0 = now();
-                self.pinINT.wait_for_any_edge().await;
+                    #[cfg(feature = "defmt")]
+                    let t0 = Instant::now();
+                    pinINT.wait_for_any_edge().await
+                        .map_err(|_| Error::Pin)?;
 
-                debug!("Woke up to INT edge (now {}; slept {}ms)", if self.pinINT.is_low() {"low"} else {"high"}, (now() - t0).to_millis());
+                    #[cfg(feature = "defmt")]
+                    debug!("Woke up to INT edge (now {}; slept {}ms)", if matches!(pinINT.is_low(), Ok(true)) {"low"} else {"high"}, t0.elapsed().as_millis());
+                },
+                Wake::Poll(interval) => {
+                    Timer::after(interval).await;
+                }
             }
         }
     }
 
-    pub fn stop(self) -> Result<([VL;N], Input<'static>)> {
-        let vls = array_try_map(self.ulds, |x| {
-            let uld = x.stop()?;
-            Ok( VL::recreate(uld) )
-        })?;
-
-        Ok( (vls, self.pinINT) )
+    pub(crate) fn into_pin(self) -> Option<INT> {
+        self.wake.into_pin()
     }
 }
 
@@ -0,0 +1,122 @@
+/*
+* Multiple boards, each with its own config - also of different resolutions (e.g. a forward
+* facing board at 8x8, and side boards at 4x4).
+*
+* Like 'RangingFlock<N,DIM>', but the resolution is per board, and known at runtime (see
+* 'vl53l5cx_uld::State_Ranging_Dyn'). The scheduling is the same.
+*/
+#![cfg(feature = "flock")]
+
+use embassy_time::{with_timeout, Duration, Instant};
+
+use embedded_hal::digital::InputPin;
+use embedded_hal_async::digital::Wait;
+
+use vl53l5cx_uld::{
+    units::TempC,
+    Error,
+    FrameStats,
+    RangingConfigDyn,
+    Resolution,
+    Result,
+    ResultsDataDyn,
+    State_Ranging_Dyn,
+};
+
+use crate::{
+    ranging_flock::{array_try_map, Scheduler},
+    wake::{NoInt, Wake},
+    VL
+};
+
+/*
+* Note: 'res' is of the resolution of the board it came from ('board_index').
+*/
+#[derive(Clone, Debug)]
+pub struct FlockResultsDyn {
+    pub board_index: usize,
+    pub res: ResultsDataDyn,
+    pub temp_degc: TempC,
+    pub time_stamp: Instant,
+    pub streamcount: u8,    // the board's frame counter (0..=254; wraps)
+}
+
+impl FlockResultsDyn {
+    /*
+    * A buffer for '.get_data_into()'; keep it over the frames. The 'res' gets replaced, if a board
+    * of the other resolution is read into it.
+    */
+    pub fn empty() -> Self {
+        Self{ board_index: 0, res: ResultsDataDyn::empty(Resolution::_8X8), temp_degc: TempC(0), time_stamp: Instant::from_ticks(0), streamcount: 0 }
+    }
+}
+
+pub struct RangingFlockDyn<const N: usize, INT = NoInt> {
+    ulds: [State_Ranging_Dyn;N],
+    sched: Scheduler<N,INT>,
+}
+
+impl<const N: usize, INT: InputPin + Wait> RangingFlockDyn<N,INT> {
+
+    pub(crate) fn start(vls: [VL;N], cfgs: &[RangingConfigDyn;N], wake: Wake<INT>) -> Result<Self> {
+        let mut i = 0;
+        let ulds: [State_Ranging_Dyn;N] = array_try_map(vls, |x| {
+            let r = x.into_uld().start_ranging_dyn(&cfgs[i]);
+            i += 1;
+            r
+        })?;
+
+        Ok(Self{
+            ulds,
+            sched: Scheduler::new(wake),
+        })
+    }
+
+    pub async fn get_data(&mut self) -> Result<FlockResultsDyn> {
+        let mut out = FlockResultsDyn::empty();
+        self.get_data_into(&mut out).await?;
+        Ok(out)
+    }
+
+    /*
+    * Like '.get_data()', but gives up after 'timeout' ('Error::NoDataInTime').
+    */
+    pub async fn get_data_with_timeout(&mut self, timeout: Duration) -> Result<FlockResultsDyn> {
+        let mut out = FlockResultsDyn::empty();
+        match with_timeout(timeout, self.get_data_into(&mut out)).await {
+            Ok(r) => r.map(|_| out),
+            Err(_) => Err(Error::NoDataInTime),
+        }
+    }
+
+    pub async fn get_data_into(&mut self, out: &mut FlockResultsDyn) -> Result<()> {
+        let i = self.sched.next(&mut self.ulds).await?;
+        let time_stamp = Instant::now();
+
+        out.temp_degc = self.ulds[i].get_data_into(&mut out.res)?;
+        out.board_index = i;
+        out.time_stamp = time_stamp;
+        out.streamcount = self.ulds[i].streamcount();
+        Ok(())
+    }
+
+    pub fn resolution(&self, i: usize) -> Resolution {
+        self.ulds[i].resolution()
+    }
+
+    pub fn frame_stats(&self, i: usize) -> &FrameStats {
+        self.ulds[i].frame_stats()
+    }
+
+    /*
+    * Stop the boards. The 'INT' pin is given back, unless polling.
+    */
+    pub fn stop(self) -> Result<([VL;N], Option<INT>)> {
+        let vls = array_try_map(self.ulds, |x| {
+            let uld = x.stop()?;
+            Ok( VL::recreate(uld) )
+        })?;
+
+        Ok( (vls, self.sched.into_pin()) )
+    }
+}
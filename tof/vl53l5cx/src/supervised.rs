@@ -0,0 +1,254 @@
+/*
+* Single board ranging, with a watchdog: if the sensor stalls, it's power cycled, set up again and
+* the ranging restarted - with the same config (and calibration).
+*
+* Stalls are seen as:
+*   - no INT within the expected time (a few frame periods, learned from the 'RangingConfig')
+*   - INT, but no new data
+*   - an error while reading the data (e.g. a glitched bus), also when read again
+*
+* A single failed read is retried, without a power cycle. If the sensor answers but the frame is
+* gone, we wait for the next one.
+*
+* A recovery is reported to the caller as an event; the results buffer is then not filled. If
+* the recovery itself fails (I2C, 'Error::Pin' for 'PWR_EN'/'LPn', or re-applying the
+* calibration), the error is returned and the next call tries again.
+*
+* Note: 'PWR_EN' powers all the boards wired to it. Only use this with a single board on the line.
+*/
+#![cfg(feature = "supervised")]
+
+#[cfg(feature = "defmt")]
+use defmt::{info, warn};
+
+use embassy_time::{with_timeout, Duration, Instant, Timer};
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::digital::Wait;
+
+use vl53l5cx_uld::{
+    CalibrationBlob,
+    Error,
+    FrameStats,
+    I2cAddr,
+    RangingConfig,
+    Result,
+    State_Ranging,
+};
+
+use crate::{
+    ranging::{Ranging, SoloResults},
+    wake::{frame_period, Wake},
+    I2cBus,
+    VL,
+};
+
+/*
+* What's needed to bring the board back.
+*/
+pub struct Recovery<B: 'static, O> {
+    pub i2c_shared: &'static B,
+    pub i2c_addr: I2cAddr,
+    pub PWR_EN: O,
+    pub LPn: O,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Stall {
+    NoInt,              // no INT in time (and no data, either)
+    NoData,             // INT, but the sensor had no new frame
+    Failed(Error),      // reading the data failed
+    Retry,              // an earlier recovery failed
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Event {
+    Data,               // results were written
+    Recovered(Stall),   // the sensor stalled, and was brought back; no results
+}
+
+/*
+* The board is either ranging, or down (the last recovery failed). Down, we keep the 'INT' pin for
+* when it's back.
+*/
+#[allow(clippy::large_enum_variant)]    // no heap; the 'Option<Ranging>' this replaced took the same
+enum Session<INT, const DIM: usize> {
+    Running(Ranging<DIM,INT>),
+    Down(Wake<INT>),
+}
+
+impl<INT: Wait, const DIM: usize> Session<INT,DIM> {
+    fn into_wake(self) -> Wake<INT> {
+        match self {
+            Self::Running(r) => r.abandon(),
+            Self::Down(wake) => wake,
+        }
+    }
+}
+
+pub struct Supervised<B: 'static, O, INT, const DIM: usize> {
+    session: Session<INT,DIM>,
+    rec: Recovery<B,O>,
+    cfg: RangingConfig<DIM>,
+    calibration: CalibrationBlob,
+    timeout: Option<Duration>,
+    recoveries: u32,
+}
+
+impl<B, O, INT, const DIM: usize> Supervised<B, O, INT, DIM>
+    where B: I2cBus + 'static, O: OutputPin, INT: Wait
+{
+    /*
+    * Start ranging on 'vl'. Its calibration (as it is now) is re-applied after each recovery.
+    */
+    pub fn start(vl: VL, cfg: &RangingConfig<DIM>, pinINT: INT, rec: Recovery<B,O>) -> Result<Self> {
+        let calibration = vl.calibration();
+        let ranging = Ranging::start(vl, cfg, Wake::Int(pinINT))?;
+
+        Ok(Self{
+            session: Session::Running(ranging),
+            rec,
+            cfg: cfg.clone(),
+            calibration,
+            timeout: default_timeout(cfg),
+            recoveries: 0,
+        })
+    }
+
+    /*
+    * Override the time to wait for INT. Needed e.g. with detection thresholds, where INT only
+    * comes when they trip (by default, there's no timeout for them).
+    */
+    pub fn with_timeout_ms(self, ms: u32) -> Self {
+        Self{ timeout: Some(Duration::from_millis(ms as u64)), ..self }
+    }
+
+    pub async fn get_data_into(&mut self, out: &mut SoloResults<DIM>) -> Result<Event> {
+        let stall = match self.session {
+            Session::Down(_) => Stall::Retry,
+            Session::Running(ref mut r) => {
+                let mut retried = false;
+                loop {
+                    let got = match self.timeout {
+                        Some(t) => with_timeout(t, r.wait_and_read(out)).await.ok(),
+                        None => Some(r.wait_and_read(out).await),
+                    };
+
+                    match got {
+                        Some(Ok(true)) => return Ok(Event::Data),
+                        Some(Ok(false)) => break Stall::NoData,
+
+                        // Once per call, read again before power cycling. The frame seen ready
+                        // (if any) is still there.
+                        Some(Err(_e)) if !retried => {
+                            #[cfg(feature = "defmt")]
+                            warn!("Reading failed ({}); trying again", _e);
+                            retried = true;
+
+                            match r.read_into(out, Instant::now()) {
+                                Ok(true) => return Ok(Event::Data),
+                                Ok(false) => continue,      // the sensor answers; wait for the next frame
+                                Err(e) => break Stall::Failed(e),
+                            }
+                        },
+                        Some(Err(e)) => break Stall::Failed(e),
+
+                        // INT may have been missed; ask the sensor
+                        None => match r.read_into(out, Instant::now()) {
+                            Ok(true) => return Ok(Event::Data),
+                            Ok(false) => break Stall::NoInt,
+                            Err(e) => break Stall::Failed(e),
+                        }
+                    }
+                }
+            }
+        };
+
+        #[cfg(feature = "defmt")]
+        warn!("Sensor stalled ({}); recovering", stall);
+        self.recover().await?;
+        Ok(Event::Recovered(stall))
+    }
+
+    /*
+    * Power cycle the board, set it up and restart the ranging.
+    */
+    async fn recover(&mut self) -> Result<()> {
+        self.recoveries += 1;
+        let restarted = self.restart().await;
+
+        // Move the pin over to the new session. 'Down' with a dummy stands in for the moment
+        // (no '.await', nothing failing); the session always has the pin, also if we're dropped
+        // during the restart.
+        let wake = core::mem::replace(&mut self.session, Session::Down(Wake::Poll(Duration::MIN))).into_wake();
+
+        match restarted {
+            Ok(uld) => {
+                self.session = Session::Running(Ranging::from_parts(uld, wake));
+                #[cfg(feature = "defmt")]
+                info!("Sensor recovered (#{})", self.recoveries);
+                Ok(())
+            },
+            Err(e) => {
+                self.session = Session::Down(wake);
+                Err(e)
+            }
+        }
+    }
+
+    /*
+    * The power cycle and setup of '.recover()'. The session is left as it is; the sensor is
+    * not asked through it.
+    */
+    async fn restart(&mut self) -> Result<State_Ranging<DIM>> {
+        let rec = &mut self.rec;
+        rec.LPn.set_low().map_err(|_| Error::Pin)?;     // don't feed the chip via its pins, while it's off
+        rec.PWR_EN.set_low().map_err(|_| Error::Pin)?;
+        Timer::after_millis(10).await;      // 10ms based on UM2884 (PDF; 18pp) Rev. 6, Chapter 4.2
+        rec.PWR_EN.set_high().map_err(|_| Error::Pin)?;
+        rec.LPn.set_high().map_err(|_| Error::Pin)?;
+
+        let mut vl = VL::new_and_setup(rec.i2c_shared, &rec.i2c_addr)?;
+        vl.set_calibration(&self.calibration)?;
+        vl.into_uld().start_ranging(&self.cfg)
+    }
+
+    /*
+    * Frames of the current ranging session (restarts with each recovery).
+    */
+    pub fn frame_stats(&self) -> Option<&FrameStats> {
+        match self.session {
+            Session::Running(ref r) => Some(r.frame_stats()),
+            Session::Down(_) => None,
+        }
+    }
+
+    pub fn recoveries(&self) -> u32 {
+        self.recoveries
+    }
+
+    /*
+    * Stop ranging. If the sensor is down (the last recovery failed), it's set up anew.
+    */
+    pub fn stop(self) -> Result<(VL, Recovery<B,O>)> {
+        let vl = match self.session {
+            Session::Running(r) => r.stop()?,
+            Session::Down(_) => VL::new_and_setup(self.rec.i2c_shared, &self.rec.i2c_addr)?,
+        };
+        Ok((vl, self.rec))
+    }
+}
+
+/*
+* Three frame periods, plus some for the I2C and first frame.
+*
+* With detection thresholds, INT only comes for frames that trip them; no timeout.
+*/
+fn default_timeout<const DIM: usize>(cfg: &RangingConfig<DIM>) -> Option<Duration> {
+    #[cfg(feature = "detection_thresholds")]
+    if cfg.thresholds().is_some() { return None }
+
+    Some(frame_period(cfg) * 3 + Duration::from_millis(100))
+}
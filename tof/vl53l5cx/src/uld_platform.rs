@@ -1,23 +1,31 @@
 /*
 * 'Platform' implementation for the VL53L5CX ULD interface.
 *
-* For access to the I2C bus, a 'RefCell' is used. The intended use is Embassy tasks, on the same
-* priority, where multiple devices can borrow the bus, but not across 'await' boundaries.
+* The I2C bus is shared via an 'I2cBus' ('RefCell', or a mutex; see 'i2c_bus.rs'). It's taken for
+* each transaction separately, so that other devices on the bus get their turns in between.
+*
+* The bus is any blocking 'embedded_hal::i2c::I2c'; delays come from 'embassy-time' (busy waiting).
+*
+* I2C errors are given to the ULD as 'Err(())'; the call in question then fails with 'Error::Generic'
+* (within ULD C calls) or 'Error::PlatformI2c' (ping, I2C address change). The HAL's error kind is
+* only logged.
 */
 #[cfg(feature = "defmt")]
 #[allow(unused_imports)]
 use defmt::{info, debug, error, warn, trace, panic};
 
-use core::{
-    cell::RefCell,
-    mem::MaybeUninit,
-};
+use core::mem::MaybeUninit;
+
+use embassy_time::Delay;
 
-use esp_hal::{
-    delay::Delay,
-    i2c::{I2c, Instance},
-    Blocking
+use embedded_hal::{
+    delay::DelayNs,
+    i2c::I2c,
 };
+#[cfg(feature = "defmt")]
+use embedded_hal::i2c::Error as _;
+
+use crate::i2c_bus::I2cBus;
 
 use vl53l5cx_uld::{
     DEFAULT_I2C_ADDR,
@@ -25,10 +33,9 @@ use vl53l5cx_uld::{
     Platform
 };
 
-// NOTE: THIS WILL CHANGE >0.21.1. See 'vl53l5cx_uld' for "next" platform code.
-//
-// Maximum sizes for I2C writes and reads, via 'esp-hal' (0.19.0). Unfortunately, it does not
-// expose these as values we could read (the values are burnt-in).
+// Maximum sizes for I2C writes and reads. These are what 'esp-hal' (0.19.0 .. 0.21.1) manages;
+// it does not expose them as values we could read (the values are burnt-in). Other HALs can
+// likely do more, but the chunking costs little.
 //
 const MAX_WR_LEN: usize = 254;
 const MAX_RD_LEN: usize = 254;      // trying to read longer than this would err with 'ExceedingFifo'
@@ -38,8 +45,8 @@ const TRACE_HEAD_N:usize=20;        // Number of first bytes to show
 
 /*
 */
-pub(crate) struct Pl<'a, T: Instance> {
-    i2c_shared: &'a RefCell<I2c<'a, T, Blocking>>,
+pub(crate) struct Pl<'a, B> {
+    i2c_shared: &'a B,
     i2c_addr: I2cAddr
 }
 
@@ -47,10 +54,10 @@ pub(crate) struct Pl<'a, T: Instance> {
 //  - "Lost in lifetimes" (answer)
 //      -> https://users.rust-lang.org/t/lost-with-lifetimes/82484/4?u=asko
 //
-impl<'a,T> Pl<'a,T>
-    where T: Instance
+impl<'a,B> Pl<'a,B>
+    where B: I2cBus
 {
-    pub fn new(i2c_shared: &'a RefCell<I2c<'a, T,Blocking>>) -> Self {
+    pub fn new(i2c_shared: &'a B) -> Self {
         Self{
             i2c_shared,
             i2c_addr: DEFAULT_I2C_ADDR     // every board starts with the default address
@@ -58,39 +65,37 @@ impl<'a,T> Pl<'a,T>
     }
 }
 
-impl<T> Platform for Pl<'_,T> where T: Instance
+impl<B> Platform for Pl<'_,B> where B: I2cBus
 {
-    // Note: With Rust Edition 2024 out, try '!' or 'Infallible' as the return type (we don't provide
-    //      errors). In Edition 2021, Rust 1.82, 'Infallible' doesn't coerce to '()' (it could),
-    //      so cannot use it now. //2-Nov-24
-
     /*
-    * ULD reads can be in sizes of 492 bytes (or more). The 'esp-hal' requires these to be handled
+    * ULD reads can be in sizes of 492 bytes (or more). E.g. 'esp-hal' requires these to be handled
     * in multiple parts.
     */
-    fn rd_bytes(&mut self, index: u16, buf: &mut [u8]) -> Result<(),() /* !*/> {
+    fn rd_bytes(&mut self, index: u16, buf: &mut [u8]) -> Result<(),()> {
+        #[cfg(feature = "defmt")]
         let index_orig = index;
 
         let chunks = buf.chunks_mut(MAX_RD_LEN);
-        let _rounds = chunks.len();
 
         // Chunks we get are *views* to the 'buf' backing them. Thus, reading to the chunk automatically
         // fills it.
         //
-        let mut i2c = self.i2c_shared.borrow_mut();
         let addr: u8 = self.i2c_addr.as_7bit();
 
         let mut index = index;    // rolled further with the chunks
 
-        for (_round,chunk) in chunks.enumerate() {
-            i2c.write_read(addr, &index.to_be_bytes(), chunk).unwrap_or_else(|e| {
+        for chunk in chunks {
+            let ret = self.i2c_shared.with_bus(|i2c| i2c.write_read(addr, &index.to_be_bytes(), chunk));
+            if let Err(_e) = ret {
                 // If we get an error, let's stop right away.
-                panic!("I2C read at {:#06x} ({=usize} bytes; chunk {}/{}) failed: {}", index_orig, chunk.len(), _round+1, _rounds, e);
-            });
+                #[cfg(feature = "defmt")]
+                warn!("I2C read at {:#06x} ({} bytes) failed: {}", index, chunk.len(), _e.kind());
+                return Err(());
+            }
 
-            index = index + chunk.len() as u16;
+            index += chunk.len() as u16;
 
-            // There should be 1.2ms between transactions, by the VL spec.
+            // There should be 1.2ms between transactions, by the VL spec. Others may use the bus, meanwhile.
             blocking_delay_ms(1);
         }
 
@@ -112,29 +117,25 @@ impl<T> Platform for Pl<'_,T> where T: Instance
     * Vendor ULD driver calls us with up to 32768 bytes, during the initialization.
     *
     * The 'esp-hal' has a limit of 254 (inclusive) bytes per write, beyond which 'ExceedingFifo'
-    * error is returned. In order to proceed, we chunk the larger parts. ((Which is kind of good,
+    * error is returned; we keep to that, with any HAL. In order to proceed, we chunk the larger parts. ((Which is kind of good,
     * since it allows us an excuse to make a limited buffer ourselves, which we need for merging
     * the writing of the address, and the data bytes, into a *single* write transaction. There are
     * no slice concatenation in 'alloc':less Rust.))
     *
-    * IF we get errors from the HAL, we panic. ULD C level would often go on for too long; it's best
-    * to stop early. CERTAIN error codes MAY lead to a single retry, if we think we have a chance
-    * to recover.
+    * IF we get errors from the HAL, we stop at that chunk, and return 'Err'. The ULD C level passes
+    * it on as a failed status (it often goes on for a while, first). CERTAIN error codes MAY lead
+    * to a single retry, if we think we have a chance to recover.
     */
-    fn wr_bytes(&mut self, index: u16, vs: &[u8]) -> Result<(),() /* !*/> {
-        let index_orig = index;
-
+    fn wr_bytes(&mut self, index: u16, vs: &[u8]) -> Result<(),()> {
         let chunks = vs.chunks(MAX_WR_LEN-2);
-        let _rounds = chunks.len();   // needs taking before we consume 'chunks' as an iterator
 
         let mut buf: [u8;MAX_WR_LEN] = unsafe { MaybeUninit::zeroed().assume_init() };
 
-        let mut i2c = self.i2c_shared.borrow_mut();
         let addr: u8 = self.i2c_addr.as_7bit();
 
         let mut index = index;    // rolled further with the chunks
 
-        for (_round,chunk) in chunks.enumerate() {
+        for chunk in chunks {
             let n: usize = chunk.len();
 
             // Writing needs to be done in one block, where the first two bytes are the index.
@@ -150,10 +151,13 @@ impl<T> Platform for Pl<'_,T> where T: Instance
                 &buf[..2+n]
             };
 
-            i2c.write(addr, &out).unwrap_or_else(|e| {
+            let ret = self.i2c_shared.with_bus(|i2c| i2c.write(addr, out));
+            if let Err(_e) = ret {
                 // If we get an error, let's stop right away.
-                panic!("I2C write to {:#06x} ({=usize} bytes; chunk {}/{}) failed: {}", index_orig, n, _round+1, _rounds, e);
-            });
+                #[cfg(feature = "defmt")]
+                warn!("I2C write to {:#06x} ({} bytes) failed: {}", index, n, _e.kind());
+                return Err(());
+            }
 
             // Give the "written" log here, separately for each chunk (clearer to follow log).
             #[cfg(feature = "defmt")]
@@ -165,7 +169,7 @@ impl<T> Platform for Pl<'_,T> where T: Instance
                 }
             }
 
-            index = index + n as u16;
+            index += n as u16;
 
             // There should be 1.3ms between transactions, by the VL spec. (see 'tBUF', p.15)
             blocking_delay_ms(1);
@@ -190,13 +194,12 @@ impl<T> Platform for Pl<'_,T> where T: Instance
     }
 }
 
+#[cfg(feature = "defmt")]
 fn slice_head(vs: &[u8],n_max: usize) -> &[u8] {
     use core::cmp::min;
     &vs[..min(vs.len(),n_max)]
 }
 
-const D_PROVIDER: Delay = Delay::new();
-
 fn blocking_delay_ms(ms: u32) {
-    D_PROVIDER.delay_millis(ms);
+    Delay.delay_ms(ms);
 }
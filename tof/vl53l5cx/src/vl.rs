@@ -4,17 +4,13 @@
 #[cfg(feature = "defmt")]
 use defmt::debug;
 
-use core::cell::RefCell;
-
-use esp_hal::{
-    gpio::Input,
-    i2c::{I2c, Instance},
-    Blocking
-};
 #[cfg(feature = "flock")]
-use esp_hal::gpio::Output;
+use embedded_hal::digital::{InputPin, OutputPin};
+#[cfg(any(feature = "single", feature = "flock"))]
+use embedded_hal_async::digital::Wait;
 
 use vl53l5cx_uld::{
+    CalibrationBlob,
     DEFAULT_I2C_ADDR,
     RangingConfig,
     Result,
@@ -24,13 +20,27 @@ use vl53l5cx_uld::{
 
 use crate::{
     I2cAddr,
+    I2cBus,
     uld_platform::Pl,
 };
+#[cfg(any(feature = "single", feature = "flock"))]
+use crate::wake::{NoInt, Wake};
+
+#[cfg(feature = "xtalk")]
+use vl53l5cx_uld::{units::PrcU8, XtalkData};
+#[cfg(feature = "persist")]
+use vl53l5cx_uld::BoardConfig;
 
 #[cfg(feature = "single")]
 use crate::ranging::Ranging;
 #[cfg(feature = "flock")]
-use crate::ranging_flock::RangingFlock;
+use crate::{
+    ranging_flock::RangingFlock,
+    ranging_flock_dyn::RangingFlockDyn,
+    wake::{frame_period, frame_period_dyn},
+};
+#[cfg(feature = "flock")]
+use vl53l5cx_uld::{Error, RangingConfigDyn};
 
 pub struct VL {
     uld: State_HP_Idle,   // initialized ULD level driver, with dedicated I2C address
@@ -39,7 +49,7 @@ pub struct VL {
 impl VL {
     // tbd. make so that caller can give either 'I2cAddr' or a reference
     //
-    pub fn new_and_setup<T: Instance + 'static>(i2c_shared: &'static RefCell<I2c<'static, T, Blocking>>,
+    pub fn new_and_setup<B: I2cBus + 'static>(i2c_shared: &'static B,
         i2c_addr: &I2cAddr
     ) -> Result<Self> {
 
@@ -53,9 +63,11 @@ impl VL {
 
         let a = i2c_addr;
         if *a != DEFAULT_I2C_ADDR {
+            #[cfg(feature = "defmt")]
             debug!("!!!! calling set_i2c_address: {}", a);
             uld.set_i2c_address(a)?;     // tbd. '.as_8bit()' if public
         }
+        #[cfg(feature = "defmt")]
         debug!("Board now reachable as: {}", i2c_addr);
 
         Ok(Self{
@@ -63,12 +75,79 @@ impl VL {
         })
     }
 
+    /*
+    * Set up a board as stored (see 'persist.rs'): its address and, if there's one, calibration.
+    * The ranging config is for the caller to use.
+    */
+    #[cfg(feature = "persist")]
+    pub fn new_and_setup_from<B: I2cBus + 'static>(i2c_shared: &'static B,
+        b: &BoardConfig
+    ) -> Result<Self> {
+        let mut vl = Self::new_and_setup(i2c_shared, &b.i2c_addr)?;
+        if let Some(ref cal) = b.calibration {
+            vl.set_calibration(cal)?;
+        }
+        Ok(vl)
+    }
+
     /*
     * Start ranging on a single board, with an 'INT' pin wired.
     */
     #[cfg(feature = "single")]
-    pub fn start_ranging<const DIM: usize>(self, cfg: &RangingConfig<DIM>, pinINT: Input<'static>) -> Result<Ranging<DIM>> {
-        Ranging::start(self, cfg, pinINT)
+    pub fn start_ranging<const DIM: usize, INT: Wait>(self, cfg: &RangingConfig<DIM>, pinINT: INT) -> Result<Ranging<DIM,INT>> {
+        Ranging::start(self, cfg, Wake::Int(pinINT))
+    }
+
+    /*
+    * Start ranging on a single board, without 'INT'. The sensor is asked for new data a few times
+    * per frame period (based on 'cfg').
+    */
+    #[cfg(feature = "single")]
+    pub fn start_ranging_polled<const DIM: usize>(self, cfg: &RangingConfig<DIM>) -> Result<Ranging<DIM,NoInt>> {
+        Ranging::start(self, cfg, Wake::poll(cfg))
+    }
+
+    /*
+    * Calibration data (offsets and xtalk) in use, for storing. Give it back by '.set_calibration()'
+    * after 'new_and_setup()'; see 'vl53l5cx_uld' for the details.
+    */
+    pub fn calibration(&self) -> CalibrationBlob {
+        self.uld.calibration()
+    }
+
+    pub fn set_calibration(&mut self, blob: &CalibrationBlob) -> Result<()> {
+        self.uld.set_calibration(blob)
+    }
+
+    /*
+    * What the sensor is set to, and a check against the config it was started with; see
+    * 'vl53l5cx_uld' for the details.
+    */
+    pub fn read_config<const DIM: usize>(&mut self) -> Result<RangingConfig<DIM>> {
+        self.uld.read_config()
+    }
+
+    pub fn verify_config<const DIM: usize>(&mut self, cfg: &RangingConfig<DIM>) -> Result<()> {
+        self.uld.verify_config(cfg)
+    }
+
+    /*
+    * Crosstalk calibration; see 'vl53l5cx_uld' for the details. Store the '.xtalk_data()' and
+    * give it back by '.set_xtalk_data()' after each 'new_and_setup()'.
+    */
+    #[cfg(feature = "xtalk")]
+    pub fn calibrate_xtalk(&mut self, reflectance: PrcU8, samples: u8, distance_mm: u16) -> Result<()> {
+        self.uld.calibrate_xtalk(reflectance, samples, distance_mm)
+    }
+
+    #[cfg(feature = "xtalk")]
+    pub fn xtalk_data(&mut self) -> Result<XtalkData> {
+        self.uld.xtalk_data()
+    }
+
+    #[cfg(feature = "xtalk")]
+    pub fn set_xtalk_data(&mut self, data: &XtalkData) -> Result<()> {
+        self.uld.set_xtalk_data(data)
     }
 
     /*
@@ -83,8 +162,8 @@ impl VL {
     }
 
     #[cfg(feature = "flock")]
-    pub fn new_flock<T, const BOARDS: usize>(LPns: [Output;BOARDS], i2c_shared: &'static RefCell<I2c<'static, T, Blocking>>, i2c_addr_gen: impl Fn(usize) -> I2cAddr) -> Result<[Self;BOARDS]>
-        where T: Instance + 'static
+    pub fn new_flock<B, O, const BOARDS: usize>(LPns: [O;BOARDS], i2c_shared: &'static B, i2c_addr_gen: impl Fn(usize) -> I2cAddr) -> Result<[Self;BOARDS]>
+        where B: I2cBus + 'static, O: OutputPin
     {
         fn array_try_map_mut_enumerated<A,B, const N: usize>(mut aa: [A;N], f: impl FnMut((usize,&mut A)) -> Result<B>) -> Result<[B;N]> {
             use arrayvec::ArrayVec;
@@ -95,12 +174,14 @@ impl VL {
         }
 
         let tmp: Result<[VL;BOARDS]> = array_try_map_mut_enumerated(LPns, #[allow(non_snake_case)] |(i,LPn)| {
-            LPn.set_high();     // enable this chip and leave it on
+            LPn.set_high().map_err(|_| Error::Pin)?;    // enable this chip and leave it on
 
             let i2c_addr = i2c_addr_gen(i);
+            #[cfg(feature = "defmt")]
             debug!("I2C ADDR: {} -> {}", i, i2c_addr);   // TEMP
             let vl = VL::new_and_setup(i2c_shared, &i2c_addr)?;
 
+            #[cfg(feature = "defmt")]
             debug!("Init of board {} succeeded", i);
             Ok(vl)
         });
@@ -116,13 +197,32 @@ impl VL {
 */
 #[cfg(feature = "flock")]
 pub trait VLsExt<const N: usize, const DIM: usize> {
-    fn start_ranging(self, cfg: &RangingConfig<DIM>, pinINT: Input<'static>) -> Result<RangingFlock<N,DIM>>;
+    fn start_ranging<INT: InputPin + Wait>(self, cfg: &RangingConfig<DIM>, pinINT: INT) -> Result<RangingFlock<N,DIM,INT>>;
+    fn start_ranging_polled(self, cfg: &RangingConfig<DIM>) -> Result<RangingFlock<N,DIM,NoInt>>;
+
+    // A config per board ('cfgs[i]' for board 'i'); same resolution
+    fn start_ranging_each<INT: InputPin + Wait>(self, cfgs: &[RangingConfig<DIM>;N], pinINT: INT) -> Result<RangingFlock<N,DIM,INT>>;
+    fn start_ranging_each_polled(self, cfgs: &[RangingConfig<DIM>;N]) -> Result<RangingFlock<N,DIM,NoInt>>;
 }
 
 #[cfg(feature = "flock")]
 impl<const N: usize, const DIM: usize> VLsExt<N,DIM> for [VL;N] {
-    fn start_ranging(self, cfg: &RangingConfig<DIM>, pinINT: Input<'static>) -> Result<RangingFlock<N,DIM>> {
-        RangingFlock::start(self, cfg, pinINT)
+    fn start_ranging<INT: InputPin + Wait>(self, cfg: &RangingConfig<DIM>, pinINT: INT) -> Result<RangingFlock<N,DIM,INT>> {
+        RangingFlock::start(self, |_| cfg, Wake::Int(pinINT))
+    }
+
+    fn start_ranging_polled(self, cfg: &RangingConfig<DIM>) -> Result<RangingFlock<N,DIM,NoInt>> {
+        RangingFlock::start(self, |_| cfg, Wake::poll(cfg))
+    }
+
+    fn start_ranging_each<INT: InputPin + Wait>(self, cfgs: &[RangingConfig<DIM>;N], pinINT: INT) -> Result<RangingFlock<N,DIM,INT>> {
+        RangingFlock::start(self, |i| &cfgs[i], Wake::Int(pinINT))
+    }
+
+    // Polled as often as the fastest board needs
+    fn start_ranging_each_polled(self, cfgs: &[RangingConfig<DIM>;N]) -> Result<RangingFlock<N,DIM,NoInt>> {
+        let period = cfgs.iter().map(frame_period).min().unwrap_or_default();
+        RangingFlock::start(self, |i| &cfgs[i], Wake::poll_for(period))
     }
     /***
     <<
@@ -134,3 +234,28 @@ impl<const N: usize, const DIM: usize> VLsExt<N,DIM> for [VL;N] {
     ...
     ***/
 }
+
+/*
+* A config per board, of any resolution ('cfgs[i]' for board 'i'). E.g. with a closure,
+* 'core::array::from_fn(|i| ...)' makes the array.
+*
+* Separate from 'VLsExt', since there's no 'DIM' to go by.
+*/
+#[cfg(feature = "flock")]
+pub trait VLsExtDyn<const N: usize> {
+    fn start_ranging_dyn<INT: InputPin + Wait>(self, cfgs: &[RangingConfigDyn;N], pinINT: INT) -> Result<RangingFlockDyn<N,INT>>;
+    fn start_ranging_dyn_polled(self, cfgs: &[RangingConfigDyn;N]) -> Result<RangingFlockDyn<N,NoInt>>;
+}
+
+#[cfg(feature = "flock")]
+impl<const N: usize> VLsExtDyn<N> for [VL;N] {
+    fn start_ranging_dyn<INT: InputPin + Wait>(self, cfgs: &[RangingConfigDyn;N], pinINT: INT) -> Result<RangingFlockDyn<N,INT>> {
+        RangingFlockDyn::start(self, cfgs, Wake::Int(pinINT))
+    }
+
+    // Polled as often as the fastest board needs
+    fn start_ranging_dyn_polled(self, cfgs: &[RangingConfigDyn;N]) -> Result<RangingFlockDyn<N,NoInt>> {
+        let period = cfgs.iter().map(frame_period_dyn).min().unwrap_or_default();
+        RangingFlockDyn::start(self, cfgs, Wake::poll_for(period))
+    }
+}
@@ -0,0 +1,91 @@
+/*
+* How the ranging learns of new data: by the 'INT' pin, or by asking the sensor every now and then.
+*
+* Polling is for boards that have no GPIO free for 'INT'. It costs a few bytes of I2C each time,
+* and the time stamps are only as accurate as the poll interval.
+*/
+use core::convert::Infallible;
+
+use embassy_time::Duration;
+
+use embedded_hal::digital::{ErrorType, InputPin};
+use embedded_hal_async::digital::Wait;
+
+use vl53l5cx_uld::{
+    units::HzU8,
+    Mode,
+    RangingConfig,
+};
+#[cfg(feature = "flock")]
+use vl53l5cx_uld::RangingConfigDyn;
+
+pub(crate) enum Wake<INT> {
+    Int(INT),
+    Poll(Duration),
+}
+
+impl<INT> Wake<INT> {
+    pub(crate) fn poll<const DIM: usize>(cfg: &RangingConfig<DIM>) -> Self {
+        Self::poll_for(frame_period(cfg))
+    }
+
+    /*
+    * Poll four times per frame, but not more often than each 2ms. With boards of different
+    * frequencies, give the shortest period.
+    */
+    pub(crate) fn poll_for(period: Duration) -> Self {
+        let ms = period.as_millis() / 4;
+        Self::Poll(Duration::from_millis(ms.max(2)))
+    }
+
+    #[cfg(feature = "flock")]
+    pub(crate) fn into_pin(self) -> Option<INT> {
+        match self {
+            Self::Int(pin) => Some(pin),
+            Self::Poll(_) => None,
+        }
+    }
+}
+
+/*
+* The "pin" type of polled ranging ('Ranging<DIM,NoInt>', 'RangingFlock<N,DIM,NoInt>'). There are
+* no values of it; it's never waited on.
+*/
+pub enum NoInt {}
+
+impl ErrorType for NoInt {
+    type Error = Infallible;
+}
+
+impl InputPin for NoInt {
+    fn is_high(&mut self) -> Result<bool, Infallible> { match *self {} }
+    fn is_low(&mut self) -> Result<bool, Infallible> { match *self {} }
+}
+
+impl Wait for NoInt {
+    async fn wait_for_high(&mut self) -> Result<(), Infallible> { match *self {} }
+    async fn wait_for_low(&mut self) -> Result<(), Infallible> { match *self {} }
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Infallible> { match *self {} }
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Infallible> { match *self {} }
+    async fn wait_for_any_edge(&mut self) -> Result<(), Infallible> { match *self {} }
+}
+
+/*
+* Time between frames. 'CONTINUOUS' ranges at the sensor's frequency, which is 1 Hz unless an
+* earlier 'AUTONOMOUS' config changed it; we presume 1 Hz.
+*/
+pub(crate) fn frame_period<const DIM: usize>(cfg: &RangingConfig<DIM>) -> Duration {
+    let ms: u64 = match cfg.mode() {
+        Mode::AUTONOMOUS(_, HzU8(hz)) => 1000 / (hz.max(1) as u64),
+        Mode::CONTINUOUS => 1000,
+    };
+    Duration::from_millis(ms)
+}
+
+#[cfg(feature = "flock")]
+pub(crate) fn frame_period_dyn(cfg: &RangingConfigDyn) -> Duration {
+    match cfg {
+        RangingConfigDyn::_4X4(c) => frame_period(c),
+        RangingConfigDyn::_8X8(c) => frame_period(c),
+    }
+}
@@ -0,0 +1,244 @@
+/*
+* Host side tests of 'RangingFlock' scheduling: simulated sensors on a mock I2C bus, with mock
+* LPn and INT pins.
+*
+*   $ cargo test --target x86_64-unknown-linux-gnu --features flock,distance_mm --test flock
+*/
+#[path = "../../vl53l5cx_uld/tests/sim/mod.rs"]
+mod sim;
+mod mock;
+
+use embassy_futures::{block_on, join::join};
+use embassy_time::{Duration, Timer};
+
+use mock::{Int, SimBus};
+use sim::Sim;
+use vl53l5cx::{
+    units::*,
+    Error,
+    FlockResults,
+    FlockResultsDyn,
+    I2cAddr,
+    Mode::*,
+    RangingConfig,
+    RangingConfigDyn,
+    RangingFlock,
+    Resolution,
+    ResultsDataDyn,
+    VL,
+    VLsExt as _,
+    VLsExtDyn as _,
+};
+
+const BASE: u8 = 0x30;
+
+fn cfg() -> RangingConfig<4> {
+    RangingConfig::<4>::default()
+        .with_mode(AUTONOMOUS(5.ms(), HzU8(10)))    // polled: every 25ms
+}
+
+/*
+* Boards that only make frames when told to (by the test, or an INT step).
+*/
+fn flock<const N: usize>() -> ([Sim;N], [VL;N]) {
+    let sims: [Sim;N] = core::array::from_fn(|_| {
+        let s = Sim::flat(1000);
+        s.hold_frames(true);
+        s.produce_frames(1);    // differs from the ULD's idea of the frame counter, at start
+        s
+    });
+    let (bus, lpns) = SimBus::new(&sims);
+
+    let vls = VL::new_flock(lpns, bus, |i| I2cAddr::from_7bit(BASE + i as u8)).unwrap();
+    (sims, vls)
+}
+
+fn get<const N: usize, INT>(ring: &mut RangingFlock<N,4,INT>) -> FlockResults<4>
+    where INT: embedded_hal::digital::InputPin + embedded_hal_async::digital::Wait
+{
+    block_on(ring.get_data_with_timeout(Duration::from_secs(1))).unwrap()
+}
+
+// The frame that's there at the start (normally not valid); each board gives one, without waiting.
+fn skip_first<const N: usize, INT>(ring: &mut RangingFlock<N,4,INT>)
+    where INT: embedded_hal::digital::InputPin + embedded_hal_async::digital::Wait
+{
+    let mut got: Vec<usize> = (0..N).map(|_| get(ring).board_index).collect();
+    got.sort();
+    assert_eq!(got, (0..N).collect::<Vec<_>>());
+}
+
+#[test]
+fn boards_get_their_addresses() {
+    let (sims, _vls) = flock::<3>();
+
+    for (i,s) in sims.iter().enumerate() {
+        assert_eq!(s.dev_addr(), BASE + i as u8);
+        assert!(s.fw_uploaded());
+    }
+}
+
+#[test]
+fn ready_boards_are_all_given() {
+    let (sims, vls) = flock::<3>();
+    let int = Int::default();
+    let mut ring = vls.start_ranging(&cfg(), int.clone()).unwrap();
+    skip_first(&mut ring);
+
+    let (a, b) = (sims[0].clone(), sims[2].clone());
+    int.then(move || { a.produce_frames(1); b.produce_frames(1); });
+
+    let mut got = vec![get(&mut ring).board_index, get(&mut ring).board_index];
+    got.sort();
+    assert_eq!(got, [0, 2]);
+    assert_eq!(int.steps_left(), 0);
+
+    // Nothing more: times out, and the next frame is still given
+    let r = block_on(ring.get_data_with_timeout(Duration::from_millis(50)));
+    assert_eq!(r.err(), Some(Error::NoDataInTime));
+
+    let a = sims[1].clone();
+    int.then(move || a.produce_frames(1));
+    assert_eq!(get(&mut ring).board_index, 1);
+
+    let (_vls, pin) = ring.stop().unwrap();
+    assert!(pin.is_some());
+    assert!(sims.iter().all(|s| !s.is_ranging()));
+}
+
+#[test]
+fn boards_are_given_in_the_order_seen() {
+    let (sims, vls) = flock::<3>();
+    let int = Int::default();
+    let mut ring = vls.start_ranging(&cfg(), int.clone()).unwrap();
+    skip_first(&mut ring);
+
+    // Seen in one scan (by index); the first seen is given first
+    let (a, b) = (sims[0].clone(), sims[2].clone());
+    int.then(move || { a.produce_frames(1); b.produce_frames(1); });
+    assert_eq!(get(&mut ring).board_index, 0);
+
+    // Seen while #2 is pending; comes after it
+    sims[1].produce_frames(1);
+    assert_eq!(get(&mut ring).board_index, 2);
+    assert_eq!(get(&mut ring).board_index, 1);
+}
+
+#[test]
+fn pending_board_is_time_stamped_at_read() {
+    let (sims, vls) = flock::<2>();
+    let int = Int::default();
+    let mut ring = vls.start_ranging(&cfg(), int.clone()).unwrap();
+    skip_first(&mut ring);
+
+    let (a, b) = (sims[0].clone(), sims[1].clone());
+    int.then(move || { a.produce_frames(1); b.produce_frames(1); });
+
+    let first = get(&mut ring);
+    block_on(Timer::after_millis(20));
+    let second = get(&mut ring);
+
+    assert!(second.time_stamp - first.time_stamp >= Duration::from_millis(20));
+}
+
+#[test]
+fn pending_board_is_read_once() {
+    let (sims, vls) = flock::<2>();
+    let int = Int::default();
+    let mut ring = vls.start_ranging(&cfg(), int.clone()).unwrap();
+    skip_first(&mut ring);
+
+    let (a, b) = (sims[0].clone(), sims[1].clone());
+    int.then(move || { a.produce_frames(1); b.produce_frames(1); });
+
+    let first = get(&mut ring).board_index;
+    let other = 1 - first;
+
+    // The other board makes one more frame, while pending. It's given without waiting for INT;
+    // its results are the newer frame.
+    sims[other].produce_frames(1);
+    let frames_before = sims[other].frames_read();
+
+    assert_eq!(get(&mut ring).board_index, other);
+    assert_eq!(sims[other].frames_read(), frames_before + 1);
+
+    let st = ring.frame_stats(other);
+    assert_eq!((st.frames, st.stale), (2, 1));
+    assert_eq!(ring.frame_stats(first).stale, 0);
+}
+
+#[test]
+fn polled_flock_finds_frames() {
+    let (sims, vls) = flock::<2>();
+    let mut ring = vls.start_ranging_polled(&cfg()).unwrap();
+    skip_first(&mut ring);
+
+    let a = sims[1].clone();
+    let (r, _) = block_on(join(
+        ring.get_data_with_timeout(Duration::from_secs(1)),
+        async move {
+            Timer::after_millis(30).await;
+            a.produce_frames(1);
+        }
+    ));
+    assert_eq!(r.unwrap().board_index, 1);
+
+    let (_vls, pin) = ring.stop().unwrap();
+    assert!(pin.is_none());
+}
+
+#[test]
+fn boards_of_different_resolutions() {
+    let (sims, vls) = flock::<2>();
+    let cfgs: [RangingConfigDyn;2] = [
+        RangingConfig::<8>::default().with_mode(AUTONOMOUS(5.ms(), HzU8(5))).into(),
+        cfg().into(),
+    ];
+    let int = Int::default();
+    let mut ring = vls.start_ranging_dyn(&cfgs, int.clone()).unwrap();
+    assert_eq!((ring.resolution(0), ring.resolution(1)), (Resolution::_8X8, Resolution::_4X4));
+
+    // Same buffer, both resolutions
+    let mut out = FlockResultsDyn::empty();
+    let mut check = |ring: &mut vl53l5cx::RangingFlockDyn<2,Int>| {
+        block_on(ring.get_data_into(&mut out)).unwrap();
+        assert_eq!(out.res.resolution(), ring.resolution(out.board_index));
+
+        let corner = match out.res {
+            ResultsDataDyn::_4X4(ref r) => r.distance_mm[0][3][3],
+            ResultsDataDyn::_8X8(ref r) => r.distance_mm[0][7][7],
+        };
+        assert_eq!(corner, 1000);
+        out.board_index
+    };
+
+    let mut got = vec![check(&mut ring), check(&mut ring)];     // the frames there at the start
+    got.sort();
+    assert_eq!(got, [0, 1]);
+
+    let (a, b) = (sims[0].clone(), sims[1].clone());
+    int.then(move || { a.produce_frames(1); b.produce_frames(1); });
+
+    let mut got = vec![check(&mut ring), check(&mut ring)];
+    got.sort();
+    assert_eq!(got, [0, 1]);
+
+    let (_vls, pin) = ring.stop().unwrap();
+    assert!(pin.is_some());
+}
+
+#[test]
+fn each_board_its_own_config() {
+    let (_sims, vls) = flock::<2>();
+    let cfgs = [
+        cfg(),
+        RangingConfig::<4>::default().with_mode(AUTONOMOUS(5.ms(), HzU8(30))),
+    ];
+    let mut ring = vls.start_ranging_each_polled(&cfgs).unwrap();
+    skip_first(&mut ring);
+
+    let (mut vls, _) = ring.stop().unwrap();
+    vls[0].verify_config(&cfgs[0]).unwrap();
+    vls[1].verify_config(&cfgs[1]).unwrap();
+    assert_eq!(vls[0].verify_config(&cfgs[1]), Err(Error::ConfigMismatch));
+}
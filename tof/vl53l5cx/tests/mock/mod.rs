@@ -0,0 +1,187 @@
+/*
+* Host side stand-ins for the HAL: an I2C bus with simulated sensors on it, LPn (and PWR_EN) pins
+* and an INT pin.
+*
+* The sensors are 'vl53l5cx_uld's simulation ('../vl53l5cx_uld/tests/sim/'). Like on the real
+* bus, a sensor with its LPn low doesn't answer, and one answers only at its current address.
+*
+* INT is scripted: each wait for an edge runs the next step given by '.then()' (e.g. making some
+* sensors produce a frame), and returns. Without steps, the wait never returns.
+*
+* Usage (from a test file):
+*   <<
+*       #[path = "../../vl53l5cx_uld/tests/sim/mod.rs"]
+*       mod sim;
+*       mod mock;
+*   <<
+*/
+#![allow(dead_code)]
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    convert::Infallible,
+    future::pending,
+    rc::Rc,
+};
+
+use embedded_hal::{
+    digital::{self, InputPin, OutputPin},
+    i2c::{self, ErrorKind, I2c, NoAcknowledgeSource, Operation},
+};
+use embedded_hal_async::digital::Wait;
+
+use vl53l5cx_uld::{I2cAddr, Platform};
+
+use crate::sim::{Sim, SimPl};
+
+/*
+* An output pin; the test (and the bus) can see its level.
+*/
+type Hook = Box<dyn Fn()>;
+
+#[derive(Clone, Default)]
+pub struct Pin(Rc<Cell<bool>>, Rc<RefCell<Option<Hook>>>);
+
+impl Pin {
+    pub fn is_set_high(&self) -> bool { self.0.get() }
+
+    /*
+    * Run 'f' each time the pin is driven low; e.g. a 'PWR_EN' line cutting a sensor's power.
+    */
+    pub fn on_low(&self, f: impl Fn() + 'static) {
+        *self.1.borrow_mut() = Some(Box::new(f));
+    }
+}
+
+impl digital::ErrorType for Pin {
+    type Error = Infallible;
+}
+
+impl OutputPin for Pin {
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        self.0.set(false);
+        if let Some(f) = self.1.borrow().as_ref() { f() }
+        Ok(())
+    }
+    fn set_high(&mut self) -> Result<(), Infallible> { self.0.set(true); Ok(()) }
+}
+
+pub struct SimBus {
+    boards: Vec<(SimPl, Pin)>,  // sensor, its LPn
+    log: Vec<u8>,               // addresses of all transactions, in order
+}
+
+#[derive(Debug)]
+pub struct Nack;
+
+impl i2c::Error for Nack {
+    fn kind(&self) -> ErrorKind { ErrorKind::NoAcknowledge(NoAcknowledgeSource::Unknown) }
+}
+
+impl SimBus {
+    /*
+    * The bus (for 'VL::new_flock()'), and the LPn pins of the sensors; all low.
+    */
+    pub fn new<const N: usize>(sims: &[Sim;N]) -> (&'static RefCell<SimBus>, [Pin;N]) {
+        let (bus, lpns) = Self::unshared(sims);
+        (Box::leak(Box::new(RefCell::new(bus))), lpns)
+    }
+
+    /*
+    * The bus itself, for sharing it some other way (e.g. in a mutex).
+    */
+    pub fn unshared<const N: usize>(sims: &[Sim;N]) -> (SimBus, [Pin;N]) {
+        let lpns: [Pin;N] = core::array::from_fn(|_| Pin::default());
+        let boards = sims.iter().zip(lpns.iter())
+            .map(|(s,p)| (s.platform(), p.clone()))
+            .collect();
+
+        (SimBus{ boards, log: Vec::new() }, lpns)
+    }
+
+    // Number of transactions (ACKed or not) to 'addr'
+    pub fn transfers_to(&self, addr: u8) -> usize {
+        self.log.iter().filter(|a| **a == addr).count()
+    }
+
+    // Every sensor that's enabled, and at 'addr', takes part. NACK if there are none.
+    fn each(&mut self, addr: u8, mut f: impl FnMut(&mut SimPl) -> Result<(),()>) -> Result<(), Nack> {
+        let mut acked = false;
+
+        for (pl, lpn) in self.boards.iter_mut() {
+            if !lpn.is_set_high() { continue }
+
+            pl.addr_changed(&I2cAddr::from_7bit(addr));
+            acked |= f(pl).is_ok();
+        }
+        if acked { Ok(()) } else { Err(Nack) }
+    }
+}
+
+impl i2c::ErrorType for SimBus {
+    type Error = Nack;
+}
+
+impl I2c for SimBus {
+    fn transaction(&mut self, addr: u8, ops: &mut [Operation<'_>]) -> Result<(), Nack> {
+        self.log.push(addr);
+
+        match ops {
+            [Operation::Write(bs)] => {
+                let index = u16::from_be_bytes([bs[0], bs[1]]);
+                self.each(addr, |pl| pl.wr_bytes(index, &bs[2..]))
+            },
+            [Operation::Write(bs), Operation::Read(buf)] => {
+                let index = u16::from_be_bytes([bs[0], bs[1]]);
+                self.each(addr, |pl| pl.rd_bytes(index, buf))
+            },
+            _ => panic!("Unexpected I2C transaction")
+        }
+    }
+}
+
+/*
+* Scripted INT pin.
+*/
+type Step = Box<dyn FnOnce()>;
+
+#[derive(Clone, Default)]
+pub struct Int(Rc<RefCell<VecDeque<Step>>>);
+
+impl Int {
+    /*
+    * What happens (on the sensors) before the next edge.
+    */
+    pub fn then(&self, f: impl FnOnce() + 'static) {
+        self.0.borrow_mut().push_back(Box::new(f));
+    }
+
+    // Steps not waited for, yet
+    pub fn steps_left(&self) -> usize { self.0.borrow().len() }
+
+    async fn edge(&mut self) -> Result<(), Infallible> {
+        let step = self.0.borrow_mut().pop_front();
+        match step {
+            Some(f) => { f(); Ok(()) },
+            None => pending().await
+        }
+    }
+}
+
+impl digital::ErrorType for Int {
+    type Error = Infallible;
+}
+
+impl InputPin for Int {
+    fn is_high(&mut self) -> Result<bool, Infallible> { Ok(true) }
+    fn is_low(&mut self) -> Result<bool, Infallible> { Ok(false) }
+}
+
+impl Wait for Int {
+    async fn wait_for_high(&mut self) -> Result<(), Infallible> { self.edge().await }
+    async fn wait_for_low(&mut self) -> Result<(), Infallible> { self.edge().await }
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Infallible> { self.edge().await }
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Infallible> { self.edge().await }
+    async fn wait_for_any_edge(&mut self) -> Result<(), Infallible> { self.edge().await }
+}
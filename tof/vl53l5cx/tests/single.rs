@@ -0,0 +1,127 @@
+/*
+* Host side tests of single board 'Ranging': with INT (also a stray edge), with a timeout, and
+* polled. A simulated sensor on a mock I2C bus, with mock LPn and INT pins.
+*
+*   $ cargo test --target x86_64-unknown-linux-gnu --features single,distance_mm --test single
+*/
+#[path = "../../vl53l5cx_uld/tests/sim/mod.rs"]
+mod sim;
+mod mock;
+
+use embassy_futures::{block_on, join::join};
+use embassy_time::{Duration, Timer};
+use embedded_hal::digital::OutputPin as _;
+
+use mock::{Int, SimBus};
+use sim::Sim;
+use vl53l5cx::{
+    units::*,
+    Error,
+    I2cAddr,
+    Mode::*,
+    RangingConfig,
+    VL,
+};
+
+const ADDR: u8 = 0x30;
+
+fn cfg() -> RangingConfig<4> {
+    RangingConfig::<4>::default()
+        .with_mode(AUTONOMOUS(5.ms(), HzU8(10)))    // polled: every 25ms
+}
+
+/*
+* A board that only makes frames when told to (by the test, or an INT step). One frame is there
+* at the start.
+*/
+fn board() -> (Sim, VL) {
+    let sim = Sim::flat(1000);
+    sim.hold_frames(true);
+    sim.produce_frames(1);
+
+    let (bus, [mut lpn]) = SimBus::new(core::array::from_ref(&sim));
+    lpn.set_high().unwrap();
+
+    let vl = VL::new_and_setup(bus, &I2cAddr::from_7bit(ADDR)).unwrap();
+    (sim, vl)
+}
+
+#[test]
+fn int_gives_the_frames() {
+    let (sim, vl) = board();
+    let int = Int::default();
+    let mut r = vl.start_ranging(&cfg(), int.clone()).unwrap();
+
+    int.then(|| ());        // the frame there at the start
+    let a = sim.clone();
+    int.then(move || a.produce_frames(1));
+
+    for _ in 0..2 {
+        let res = block_on(r.get_data_with_timeout(Duration::from_secs(1))).unwrap();
+        assert_eq!(res.res.distance_mm[0][0][0], 1000);
+    }
+    assert_eq!(int.steps_left(), 0);
+    assert_eq!(sim.frames_read(), 2);
+
+    r.stop().unwrap();
+    assert!(!sim.is_ranging());
+}
+
+#[test]
+fn times_out_without_int() {
+    let (sim, vl) = board();
+    let int = Int::default();
+    let mut r = vl.start_ranging(&cfg(), int.clone()).unwrap();
+
+    let ret = block_on(r.get_data_with_timeout(Duration::from_millis(50)));
+    assert_eq!(ret.err(), Some(Error::NoDataInTime));
+
+    // Nothing is lost by the timeout; the next INT gives the frame.
+    int.then(|| ());
+    block_on(r.get_data_with_timeout(Duration::from_secs(1))).unwrap();
+    assert_eq!(sim.frames_read(), 1);
+}
+
+#[test]
+fn edge_without_data_is_skipped() {
+    let (sim, vl) = board();
+    let int = Int::default();
+    let mut r = vl.start_ranging(&cfg(), int.clone()).unwrap();
+
+    int.then(|| ());
+    block_on(r.get_data_with_timeout(Duration::from_secs(1))).unwrap();
+
+    // A glitch on INT (no frame), then a real one
+    int.then(|| ());
+    let a = sim.clone();
+    int.then(move || a.produce_frames(1));
+
+    block_on(r.get_data_with_timeout(Duration::from_secs(1))).unwrap();
+    assert_eq!(int.steps_left(), 0);
+    assert_eq!(sim.frames_read(), 2);
+}
+
+#[test]
+fn polled_finds_frames() {
+    let (sim, vl) = board();
+    let mut r = vl.start_ranging_polled(&cfg()).unwrap();
+
+    // The frame there at the start is given right away.
+    block_on(r.get_data_with_timeout(Duration::from_secs(1))).unwrap();
+
+    let ret = block_on(r.get_data_with_timeout(Duration::from_millis(50)));
+    assert_eq!(ret.err(), Some(Error::NoDataInTime));
+
+    let a = sim.clone();
+    let (ret, _) = block_on(join(
+        r.get_data_with_timeout(Duration::from_secs(1)),
+        async move {
+            Timer::after_millis(30).await;
+            a.produce_frames(1);
+        }
+    ));
+    assert_eq!(ret.unwrap().res.distance_mm[0][0][0], 1000);
+    assert_eq!(sim.frames_read(), 2);
+
+    r.stop().unwrap();
+}
@@ -0,0 +1,156 @@
+/*
+* Host side tests of 'Supervised': stalls of a simulated sensor (no INT, reads that fail also when
+* retried), the power cycle that follows, and a recovery that fails and is retried on the next call.
+* A single failed read is read again, without a power cycle.
+*
+*   $ cargo test --target x86_64-unknown-linux-gnu --features supervised,distance_mm --test supervised
+*/
+#[path = "../../vl53l5cx_uld/tests/sim/mod.rs"]
+mod sim;
+mod mock;
+
+use std::{cell::{Cell, RefCell}, rc::Rc};
+
+use embassy_futures::block_on;
+use embedded_hal::digital::OutputPin as _;
+
+use mock::{Int, Pin, SimBus};
+use sim::Sim;
+use vl53l5cx::{
+    units::*,
+    Error,
+    Event,
+    I2cAddr,
+    Mode::*,
+    RangingConfig,
+    Recovery,
+    SoloResults,
+    Stall,
+    Supervised,
+    VL,
+};
+
+const ADDR: u8 = 0x30;
+
+type Sup = Supervised<RefCell<SimBus>, Pin, Int, 4>;
+
+fn cfg() -> RangingConfig<4> {
+    RangingConfig::<4>::default()
+        .with_mode(AUTONOMOUS(5.ms(), HzU8(10)))
+}
+
+/*
+* A supervised board that only makes frames when told to. Its 'PWR_EN' cuts the sensor's power;
+* the returned counter tells how many times.
+*
+* The frame there at the start is read, so the tests begin with nothing pending.
+*/
+fn supervised(int: &Int) -> (Sim, Sup, Rc<Cell<u32>>) {
+    let sim = Sim::flat(1000);
+    sim.hold_frames(true);
+    sim.produce_frames(1);
+
+    let (bus, [mut lpn]) = SimBus::new(core::array::from_ref(&sim));
+    lpn.set_high().unwrap();
+
+    let power_offs = Rc::new(Cell::new(0));
+    let mut pwr = Pin::default();
+    let (s, n) = (sim.clone(), power_offs.clone());
+    pwr.on_low(move || { s.power_off(); n.set(n.get() + 1); });
+    pwr.set_high().unwrap();
+
+    let i2c_addr = I2cAddr::from_7bit(ADDR);
+    let vl = VL::new_and_setup(bus, &i2c_addr).unwrap();
+    let rec = Recovery{ i2c_shared: bus, i2c_addr, PWR_EN: pwr, LPn: lpn };
+
+    let mut sup = Supervised::start(vl, &cfg(), int.clone(), rec).unwrap()
+        .with_timeout_ms(50);
+
+    int.then(|| ());
+    assert_eq!(get(&mut sup), Ok(Event::Data));
+    (sim, sup, power_offs)
+}
+
+fn get(sup: &mut Sup) -> Result<Event, Error> {
+    let mut out = SoloResults::empty();
+    block_on(sup.get_data_into(&mut out))
+}
+
+// After a recovery, the board is ranging at its address, and gives frames.
+fn is_back(sim: &Sim, sup: &mut Sup, int: &Int) {
+    assert!(sim.is_ranging());
+    assert_eq!(sim.dev_addr(), ADDR);
+
+    let a = sim.clone();
+    int.then(move || a.produce_frames(1));
+    assert_eq!(get(sup), Ok(Event::Data));
+}
+
+#[test]
+fn no_int_is_recovered() {
+    let int = Int::default();
+    let (sim, mut sup, power_offs) = supervised(&int);
+
+    assert_eq!(get(&mut sup), Ok(Event::Recovered(Stall::NoInt)));
+    assert_eq!(power_offs.get(), 1);
+    assert_eq!(sup.recoveries(), 1);
+
+    is_back(&sim, &mut sup, &int);
+}
+
+#[test]
+fn failed_read_is_retried() {
+    let int = Int::default();
+    let (sim, mut sup, power_offs) = supervised(&int);
+
+    // A corrupted read; the frame is read again
+    let a = sim.clone();
+    int.then(move || { a.produce_frames(1); a.corrupt_next_frame(); });
+    assert_eq!(get(&mut sup), Ok(Event::Data));
+
+    // An I2C failure (NACK) while asking for the data
+    let a = sim.clone();
+    int.then(move || { a.produce_frames(1); a.nack_after(0); });
+    assert_eq!(get(&mut sup), Ok(Event::Data));
+
+    assert_eq!(power_offs.get(), 0);
+    assert_eq!(sup.recoveries(), 0);
+    assert_eq!(sup.frame_stats().unwrap().frames, 3);   // incl. the one of 'supervised()'
+}
+
+#[test]
+fn failed_read_again_is_recovered() {
+    let int = Int::default();
+    let (sim, mut sup, power_offs) = supervised(&int);
+
+    let a = sim.clone();
+    int.then(move || { a.produce_frames(1); a.corrupt_next_reads(2); });
+    assert_eq!(get(&mut sup), Ok(Event::Recovered(Stall::Failed(Error::CorruptedFrame))));
+    assert_eq!(power_offs.get(), 1);
+    is_back(&sim, &mut sup, &int);
+
+    assert_eq!(sup.recoveries(), 1);
+}
+
+#[test]
+fn failed_recovery_is_retried() {
+    let int = Int::default();
+    let (sim, mut sup, power_offs) = supervised(&int);
+
+    // INT, but no frame. The sensor answers the data ready check (access 0), but not the ping of
+    // the recovery.
+    let a = sim.clone();
+    int.then(move || a.nack_after(1));
+    assert_eq!(get(&mut sup), Err(Error::PlatformI2c));
+    assert!(!sim.is_ranging());
+    assert!(sup.frame_stats().is_none());
+
+    assert_eq!(get(&mut sup), Ok(Event::Recovered(Stall::Retry)));
+    assert_eq!(power_offs.get(), 2);
+    assert_eq!(sup.recoveries(), 2);
+
+    is_back(&sim, &mut sup, &int);
+
+    let (_vl, _rec) = sup.stop().unwrap();
+    assert!(!sim.is_ranging());
+}
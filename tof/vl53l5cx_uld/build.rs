@@ -100,7 +100,7 @@ fn main() -> Result<()> {
             .expect("OUT_DIR to have a value");
 
         fs::write(TMP, out_dir)
-            .expect(format!("Unable to write {TMP}").as_str());
+            .unwrap_or_else(|_| panic!("Unable to write {TMP}"));
     }
 
     //---
@@ -150,7 +150,7 @@ fn main() -> Result<()> {
         #[cfg(not(feature = "signal_per_spad"))]
         add!("VL53L5CX_DISABLE_SIGNAL_PER_SPAD");
 
-        // 'motion_indicator' support is not implemented; always disable in C
+        #[cfg(not(feature = "motion_indicator"))]
         add!("VL53L5CX_DISABLE_MOTION_INDICATOR");
 
         // Vendor docs:
@@ -177,15 +177,30 @@ fn main() -> Result<()> {
             .join("\n");
 
         fs::write(CONFIG_H_NEXT, contents)
-            .expect( &format!("Unable to write {}", CONFIG_H_NEXT) );
+            .unwrap_or_else(|_| panic!("Unable to write {}", CONFIG_H_NEXT));
     }
 
+    // MCU (bare metal) or host build?
+    //
+    // Host builds are for off-target testing ('cargo test --target x86_64-unknown-linux-gnu ...'). The vendor C code
+    // is compiled with the host 'clang', and the embedded linker scripts are left out.
+    //
+    let target = env::var("TARGET")
+        .expect("TARGET to have a value");      // e.g. "riscv32imac-unknown-none-elf"
+
+    #[allow(non_snake_case)]
+    let ON_MCU = matches!(env::var("CARGO_CFG_TARGET_OS").as_deref(), core::result::Result::Ok("none"));     // not anyhow's 'Ok'
+
     // make stuff
     //
+    // Note: 'make' gets 'TARGET' from our environment.
+    //
+    let out = format!("tmp/{target}");
+
     let st = Command::new("make")
         //.arg("-B")
-        .arg("tmp/libvendor_uld.a")    // ULD C library
-        .arg("tmp/uld_raw.rs")      // generate the ULD Rust bindings
+        .arg(format!("{out}/libvendor_uld.a"))    // ULD C library
+        .arg(format!("{out}/uld_raw.rs"))      // generate the ULD Rust bindings
         .output()
         .expect("to be able to launch `make`")   // shown if 'make' not found on PATH
         .status;
@@ -194,9 +209,22 @@ fn main() -> Result<()> {
         SUGGESTION: run 'make manual' on the command line to see more error information. \
     ");
 
-    // Link arguments
+    // Bring the target's bindings to where 'src/uld_raw.rs' points to. Only if changed, so that the time stamp
+    // doesn't cause needless rebuilds.
     //
     {
+        let bindings = fs::read(format!("{out}/uld_raw.rs"))
+            .expect("bindings to have been generated");
+
+        if fs::read("tmp/uld_raw.rs").ok().as_ref() != Some(&bindings) {
+            fs::write("tmp/uld_raw.rs", bindings)
+                .expect("Unable to write 'tmp/uld_raw.rs'");
+        }
+    }
+
+    // Link arguments
+    //
+    if ON_MCU {
         for s in [
             "-Tlinkall.x",
             "-Tdefmt.x"     // required by 'defmt'
@@ -208,7 +236,7 @@ fn main() -> Result<()> {
         println!("cargo::rustc-link-arg-tests=-Tembedded-test.x");
     }
 
-    println!("cargo:rustc-link-search=tmp");
+    println!("cargo:rustc-link-search={out}");
     println!("cargo:rustc-link-lib=static=vendor_uld");
 
     Ok(())
@@ -151,6 +151,8 @@ fn main2() -> Result<()> {
         info!(".distance_mm:      {}", res.distance_mm);
         #[cfg(feature = "reflectance_percent")]
         info!(".reflectance:      {}", res.reflectance);
+        #[cfg(feature = "motion_indicator")]
+        info!(".motion:           {}", res.motion);
     }
 
     Ok(())
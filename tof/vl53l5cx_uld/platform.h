@@ -38,8 +38,19 @@
  *          'Platform' contents fit in it.
  *          + neat (though hack); is maintainable because we can automatically check ('sizeof') that the space is enough
  */
+/*
+ * We went for the last one. The Rust 'Platform' is moved *into* the slot (by value), together
+ * with two (monomorphized) function pointers that know how to reach it and drop it. Since the
+ * vendor code passes a pointer to the slot (within the struct), moving 'VL53L5CX_Configuration'
+ * around on the Rust side is fine.
+ *
+ * Rust side checks (at compile time) that the 'Platform' fits the payload.
+ */
+#define VL53L5CX_PLATFORM_PAYLOAD 64
+
 typedef struct {
-    _Alignas(8) uint32_t _[2];      // 8 bytes; 8 aligned
+    void* _fns[2];      // cast and drop functions
+    _Alignas(8) uint8_t _payload[VL53L5CX_PLATFORM_PAYLOAD];
 } VL53L5CX_Platform;
 
 /**
@@ -0,0 +1,165 @@
+/*
+* Calibration data of a sensor, as one blob: for storing (e.g. in flash), and giving back at init.
+*
+* Each sensor has its own offset calibration, in its NVM; '.init()' reads it. Xtalk data is
+* either the vendor default, or from a calibration ('.calibrate_xtalk()', with the 'xtalk'
+* feature). Keeping both in the blob means a unit can always be brought back to the calibration
+* it was tested with - also after re-calibrating.
+*
+* Blob format (version 1):
+*   <<
+*       [0..4]      "VLCB"
+*       [4]         version
+*       [5..8]      0 (reserved)
+*       [8..496]    offset data (488 bytes; as read from the NVM)
+*       [496..1272] xtalk data (776 bytes)
+*       [1272..]    CRC-32 (IEEE; as in Ethernet, zlib) of the above; little endian
+*   <<
+*
+* The contents of the data parts are only meaningful to the sensor.
+*
+* Note: The ULD keeps both in its working memory, and sends them to the sensor whenever the
+*       resolution is set (e.g. when a ranging starts). Applying the blob sets the resolution
+*       again, so the sensor has the data right away; as the vendor's '..._set_caldata_xtalk()'
+*       does.
+*
+* Note: '.init()' still reads the offsets from the NVM, also when a blob is applied after it. The
+*       read is part of the vendor's init sequence; skipping it would need changes to the C code.
+*/
+use crate::uld_raw::{
+    vl53l5cx_get_resolution,
+    vl53l5cx_set_resolution,
+    OFFSET_BUFFER_SIZE,
+    XTALK_BUFFER_SIZE,
+    ST_OK,
+};
+use crate::{
+    error::CalibrationError,
+    state_hp_idle::State_HP_Idle,
+    Error,
+    Result,
+};
+
+const OFFSET_SIZE: usize = OFFSET_BUFFER_SIZE as usize;     // 488
+const XTALK_SIZE: usize = XTALK_BUFFER_SIZE as usize;       // 776
+
+pub const CALIBRATION_MAGIC: &[u8; 4] = b"VLCB";
+pub const CALIBRATION_VERSION: u8 = 1;
+
+const OFFSET_AT: usize = 8;
+const XTALK_AT: usize = OFFSET_AT + OFFSET_SIZE;
+const CRC_AT: usize = XTALK_AT + XTALK_SIZE;
+
+pub const CALIBRATION_SIZE: usize = CRC_AT + 4;     // 1276
+
+/*
+* A blob that has passed the checks (or was just created); always fine to apply.
+*/
+#[derive(Clone)]
+pub struct CalibrationBlob([u8; CALIBRATION_SIZE]);
+
+impl CalibrationBlob {
+    fn new(offset: &[u8; OFFSET_SIZE], xtalk: &[u8; XTALK_SIZE]) -> Self {
+        let mut bs = [0_u8; CALIBRATION_SIZE];
+        bs[..4].copy_from_slice(CALIBRATION_MAGIC);
+        bs[4] = CALIBRATION_VERSION;
+        bs[OFFSET_AT..XTALK_AT].copy_from_slice(offset);
+        bs[XTALK_AT..CRC_AT].copy_from_slice(xtalk);
+
+        let crc = crc32(&bs[..CRC_AT]);
+        bs[CRC_AT..].copy_from_slice(&crc.to_le_bytes());
+        Self(bs)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; CALIBRATION_SIZE] {
+        &self.0
+    }
+}
+
+/*
+* For reading back from storage. Rejects anything that isn't an intact, version 1 blob.
+*/
+impl TryFrom<&[u8]> for CalibrationBlob {
+    type Error = CalibrationError;
+
+    fn try_from(bs: &[u8]) -> core::result::Result<Self, Self::Error> {
+        let bs: &[u8; CALIBRATION_SIZE] = bs.try_into()
+            .map_err(|_| CalibrationError::Size{ n: bs.len() })?;
+
+        if &bs[..4] != CALIBRATION_MAGIC {
+            return Err(CalibrationError::Magic);
+        }
+        if bs[4] != CALIBRATION_VERSION {
+            return Err(CalibrationError::Version{ v: bs[4] });
+        }
+
+        let crc = u32::from_le_bytes([bs[CRC_AT], bs[CRC_AT+1], bs[CRC_AT+2], bs[CRC_AT+3]]);
+        if crc != crc32(&bs[..CRC_AT]) {
+            return Err(CalibrationError::Crc);
+        }
+        Ok(Self(*bs))
+    }
+}
+
+impl State_HP_Idle {
+    /*
+    * The calibration data in use: offsets (from this sensor's NVM, unless a blob was applied)
+    * and xtalk (default, calibrated or applied).
+    */
+    pub fn calibration(&self) -> CalibrationBlob {
+        let uld = self.borrow_uld();
+        CalibrationBlob::new(&uld.offset_data, &uld.xtalk_data)
+    }
+
+    /*
+    * Use an earlier stored calibration, instead of what '.init()' read. Call after '.init()'.
+    *
+    * The data is sent to the sensor (see above); an I2C failure gives an error. The working
+    * memory has the blob's data, also then; a later ranging start sends it again.
+    */
+    pub fn set_calibration(&mut self, blob: &CalibrationBlob) -> Result<()> {
+        let uld = self.borrow_uld_mut();
+        let bs = &blob.0;
+
+        uld.offset_data.copy_from_slice(&bs[OFFSET_AT..XTALK_AT]);
+        uld.xtalk_data.copy_from_slice(&bs[XTALK_AT..CRC_AT]);
+
+        let mut reso: u8 = 0;
+        match unsafe { vl53l5cx_get_resolution(uld, &mut reso) } {
+            ST_OK => Ok(()),
+            e => Err(Error::from_uld(e))
+        }?;
+        match unsafe { vl53l5cx_set_resolution(uld, reso) } {
+            ST_OK => Ok(()),
+            e => Err(Error::from_uld(e))
+        }
+    }
+}
+
+/*
+* CRC-32 (IEEE 802.3; reflected, polynomial 0xedb88320). Bitwise; the blobs are small and seldom.
+*
+* Also used for the records of 'persist.rs', which are checked as they stream from the flash.
+*/
+pub(crate) struct Crc32(u32);
+
+impl Crc32 {
+    pub(crate) fn new() -> Self { Self(!0) }
+
+    pub(crate) fn update(&mut self, bs: &[u8]) {
+        for b in bs {
+            self.0 ^= *b as u32;
+            for _ in 0..8 {
+                self.0 = if self.0 & 1 != 0 { (self.0 >> 1) ^ 0xedb8_8320 } else { self.0 >> 1 };
+            }
+        }
+    }
+
+    pub(crate) fn value(&self) -> u32 { !self.0 }
+}
+
+fn crc32(bs: &[u8]) -> u32 {
+    let mut c = Crc32::new();
+    c.update(bs);
+    c.value()
+}
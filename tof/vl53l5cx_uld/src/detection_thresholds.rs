@@ -0,0 +1,222 @@
+/*
+* Detection thresholds (vendor plugin)
+*
+* Normally, the sensor raises its INT pin for each completed frame. With detection thresholds
+* enabled, INT is only raised when the frame meets the configured conditions (e.g. "something
+* closer than 500mm in zone 5"). This lets the MCU sleep through uninteresting frames.
+*
+* Up to 64 thresholds can be given. Each of them applies to one zone and one measurement. Within
+* a zone, the thresholds are combined with the logic operation given by each entry ('Or', 'And');
+* the first one of a zone should use 'Or'.
+*
+* Thresholds are given in the units of the respective measurement (mm, kcps/SPAD, ...); the
+* vendor plugin converts them to the sensor's internal formats.
+*
+* Zones are numbered as in the results ('row*DIM + col'), i.e. after the 'Orientation' of the
+* 'RangingConfig' has been applied. They are turned to the sensor's own numbering when programmed.
+*
+* The thresholds are programmed while starting the ranging, after the resolution has been set.
+*
+* References:
+*   - vendor's UM2884 > "Programmable thresholds"; Rev 5
+*/
+#![cfg(feature = "detection_thresholds")]
+
+use core::mem::MaybeUninit;
+
+use crate::uld_raw::{
+    vl53l5cx_set_detection_thresholds,
+    vl53l5cx_set_detection_thresholds_enable,
+    ThresholdMeasurement as ThresholdMeasurement_R,
+    ThresholdOperation as ThresholdOperation_R,
+    ThresholdType as ThresholdType_R,
+    VL53L5CX_Configuration,
+    VL53L5CX_DetectionThresholds,
+    LAST_THRESHOLD,
+    NB_THRESHOLDS,
+    ST_OK,
+};
+use crate::{
+    orientation::Orientation,
+    ConfigError,
+    Error,
+    Result,
+};
+
+/*
+* What the threshold is checking. Values are per zone (target 0, if there are many).
+*/
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Measurement {
+    DistanceMm,
+    SignalPerSpadKcps,
+    RangeSigmaMm,
+    AmbientPerSpadKcps,
+    NbTargetDetected,
+    TargetStatus,
+    NbSpadsEnabled,
+    #[cfg(feature = "motion_indicator")]
+    MotionIndicator,
+}
+
+impl Measurement {
+    fn as_uld(&self) -> ThresholdMeasurement_R {
+        use Measurement::*;
+        match self {
+            DistanceMm => ThresholdMeasurement_R::DISTANCE_MM,
+            SignalPerSpadKcps => ThresholdMeasurement_R::SIGNAL_PER_SPAD_KCPS,
+            RangeSigmaMm => ThresholdMeasurement_R::RANGE_SIGMA_MM,
+            AmbientPerSpadKcps => ThresholdMeasurement_R::AMBIENT_PER_SPAD_KCPS,
+            NbTargetDetected => ThresholdMeasurement_R::NB_TARGET_DETECTED,
+            TargetStatus => ThresholdMeasurement_R::TARGET_STATUS,
+            NbSpadsEnabled => ThresholdMeasurement_R::NB_SPADS_ENABLED,
+            #[cfg(feature = "motion_indicator")]
+            MotionIndicator => ThresholdMeasurement_R::MOTION_INDICATOR,
+        }
+    }
+}
+
+/*
+* When does the threshold trip. Ranges are inclusive of 'low', and of 'high' (vendor docs are
+* vague on this; don't rely on the exact edges).
+*/
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Window {
+    In(i32,i32),        // low <= value <= high
+    OutOf(i32,i32),     // value < low || value > high
+    AtMost(i32),        // value <= low
+    Above(i32),         // value > high
+    Equal(i32),
+    NotEqual(i32),
+}
+
+impl Window {
+    // -> (type, low, high)
+    fn as_uld(&self) -> (ThresholdType_R, i32, i32) {
+        use Window::*;
+        match *self {
+            In(a,b) => (ThresholdType_R::IN_WINDOW, a, b),
+            OutOf(a,b) => (ThresholdType_R::OUT_OF_WINDOW, a, b),
+            AtMost(a) => (ThresholdType_R::LESS_THAN_EQUAL_MIN, a, 0),
+            Above(b) => (ThresholdType_R::GREATER_THAN_MAX, 0, b),
+            Equal(a) => (ThresholdType_R::EQUAL_MIN, a, 0),
+            NotEqual(a) => (ThresholdType_R::NOT_EQUAL_MIN, a, 0),
+        }
+    }
+}
+
+/*
+* How a threshold combines with the earlier ones of the same zone.
+*/
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Combine {
+    Or,
+    And
+}
+
+impl Combine {
+    fn as_uld(&self) -> ThresholdOperation_R {
+        match self {
+            Combine::Or => ThresholdOperation_R::OR,
+            Combine::And => ThresholdOperation_R::AND,
+        }
+    }
+}
+
+const N: usize = NB_THRESHOLDS as usize;    // 64
+
+/*
+* A list of thresholds, kept in the format the ULD C API takes them in.
+*
+* Entries are checked against the resolution (zone numbers) when the ranging starts.
+*/
+#[derive(Clone)]
+pub struct ThresholdsConfig {
+    list: [VL53L5CX_DetectionThresholds; N],
+    n: usize,
+}
+
+impl ThresholdsConfig {
+    pub fn new() -> Self {
+        // Unused entries are all zeroes, like in the vendor examples.
+        let list = unsafe { MaybeUninit::<[VL53L5CX_DetectionThresholds; N]>::zeroed().assume_init() };
+        Self { list, n: 0 }
+    }
+
+    /*
+    * Add a threshold for 'zone' (0..16 for 4x4; 0..64 for 8x8). The zone is 'row*DIM + col' of the
+    * results, as turned by the ranging's 'Orientation' (for 'Upright', same as the sensor's order).
+    *
+    * 'ConfigError::TooManyThresholds' if there already are 64 thresholds.
+    */
+    pub fn with(/*move*/ mut self, zone: u8, m: Measurement, w: Window, op: Combine) -> core::result::Result<Self, ConfigError> {
+        if self.n >= N {
+            return Err(ConfigError::TooManyThresholds{ max: N as u8 });
+        }
+
+        let (t, low, high) = w.as_uld();
+        self.list[self.n] = VL53L5CX_DetectionThresholds {
+            param_low_thresh: low,
+            param_high_thresh: high,
+            measurement: m.as_uld() as u8,
+            type_: t as u8,
+            zone_num: zone,
+            mathematic_operation: op.as_uld() as u8,
+        };
+        self.n += 1;
+        Ok(self)
+    }
+
+    pub(crate) fn try_validate<const DIM: usize>(&self) -> core::result::Result<(), ConfigError> {
+        if self.n == 0 { return Err(ConfigError::NoThresholds) }
+
+        for x in &self.list[..self.n] {
+            if (x.zone_num as usize) >= DIM*DIM {
+                return Err(ConfigError::ThresholdZoneOutOfRange{ zone: x.zone_num, zones: (DIM*DIM) as u8 });
+            }
+        }
+        Ok(())
+    }
+
+    /*
+    * Program the thresholds to the sensor, and enable them. Zones are turned from the results'
+    * numbering to the sensor's (the same as 'ResultsData::feed()' reads them).
+    *
+    * Note: '.try_validate()' has been passed; zones are in range.
+    */
+    pub(crate) fn apply<const DIM: usize>(&self, vl: &mut VL53L5CX_Configuration, o: Orientation) -> Result<()> {
+        let mut list = self.list;
+        for x in &mut list[..self.n] {
+            let z = x.zone_num as usize;
+            let (sr, sc) = o.src::<DIM>(z / DIM, z % DIM);
+            x.zone_num = (sr*DIM + sc) as u8;
+        }
+        list[self.n-1].zone_num |= LAST_THRESHOLD;    // vendor: "last threshold" marker
+
+        match unsafe { vl53l5cx_set_detection_thresholds(vl, list.as_mut_ptr()) } {
+            ST_OK => Ok(()),
+            e => Err(Error::from_uld(e))
+        }?;
+
+        enable(vl, true)
+    }
+}
+
+impl Default for ThresholdsConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/*
+* Also used for turning the thresholds off, since the sensor remembers them between rangings.
+*/
+pub(crate) fn enable(vl: &mut VL53L5CX_Configuration, on: bool) -> Result<()> {
+    match unsafe { vl53l5cx_set_detection_thresholds_enable(vl, on as u8) } {
+        ST_OK => Ok(()),
+        e => Err(Error::from_uld(e))
+    }
+}
@@ -0,0 +1,180 @@
+/*
+* Errors from the ULD C API (decoded), and from the Rust side.
+*
+* The vendor C code collects statuses by or'ing them together ('status |= ...'). A single failure
+* thus shows as one of the documented codes, but multiple failures within a call may produce
+* any value. Those are carried as 'Other'.
+*
+* Note: Our 'Platform' tunnel reports I2C failures (within ULD C calls) as 255, i.e. they show
+*       as 'Generic'.
+*/
+use core::fmt::{Display, Formatter};
+
+use crate::uld_raw::{
+    ST_TIMEOUT_ERROR,
+    ST_CORRUPTED_FRAME,
+    ST_CRC_CSUM_FAILED,
+    ST_XTALK_FAILED,
+    ST_MCU_ERROR,
+    ST_INVALID_PARAM,
+    ST_ERROR,
+};
+
+pub type Result<T> = core::result::Result<T,Error>;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    // ULD C API
+    Timeout,                // 1
+    CorruptedFrame,         // 2
+    CrcCsumFailed,          // 3
+    XtalkFailed,            // 4
+    McuError,               // 66 (0x42)
+    InvalidParam,           // 127 (0x7f)
+    Generic,                // 255; "major error (usually timeout in I2C)"
+    Other(u8),              // "combination of multiple errors"
+
+    // Rust side
+    PingMismatch{ dev_id: u8, rev_id: u8 },     // expected '(0xf0, 0x02)'
+    AddrChangeNotConfirmed, // sensor not reached after 'set_i2c_address()'
+    PlatformI2c,            // 'Platform' failed, outside of ULD C calls
+    Config(ConfigError),    // 'RangingConfig' (or calibration arguments) not acceptable; nothing sent to the sensor
+    ConfigMismatch,         // sensor runs another config than expected ('.read_config()', '.verify_config()')
+    NoDataInTime,           // no new results within the time given by the caller
+    Pin,                    // a GPIO ('INT', 'LPn', 'PWR_EN') failed; from the 'embedded-hal' level
+}
+
+/*
+* Which 'RangingConfig' (or calibration argument) constraint failed. Fields carry enough to tell
+* the user the limits.
+*/
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConfigError {
+    FrequencyOutOfRange{ dim: u8, hz: u8, max_hz: u8 },
+    IntegrationTimeOutOfRange{ ms: u16 },                   // [2..1000]
+    IntegrationExceedsWindow{ dim: u8, hz: u8, max_ms: u16 },   // all integrations + 1ms within the period
+    SharpenerOutOfRange{ prc: u8 },                         // [1..99]
+    #[cfg(feature = "motion_indicator")]
+    MotionDistanceOutOfRange{ mm: u16 },                    // [400..4000]
+    #[cfg(feature = "motion_indicator")]
+    MotionWindowEmpty{ min_mm: u16, max_mm: u16 },
+    #[cfg(feature = "motion_indicator")]
+    MotionWindowTooWide{ width_mm: u16 },                   // max 1500
+    #[cfg(feature = "detection_thresholds")]
+    NoThresholds,
+    #[cfg(feature = "detection_thresholds")]
+    TooManyThresholds{ max: u8 },                           // 64
+    #[cfg(feature = "detection_thresholds")]
+    ThresholdZoneOutOfRange{ zone: u8, zones: u8 },
+    #[cfg(feature = "xtalk")]
+    XtalkReflectanceOutOfRange{ prc: u8 },                  // [1..99]
+    #[cfg(feature = "xtalk")]
+    XtalkSamplesOutOfRange{ n: u8 },                        // [1..16]
+    #[cfg(feature = "xtalk")]
+    XtalkDistanceOutOfRange{ mm: u16 },                     // [600..3000]
+}
+
+/*
+* Why a stored 'CalibrationBlob' was not accepted.
+*/
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CalibrationError {
+    Size{ n: usize },       // not 'CALIBRATION_SIZE' bytes
+    Magic,                  // not a calibration blob
+    Version{ v: u8 },       // from a newer (or older) library
+    Crc,                    // corrupted
+}
+
+impl From<ConfigError> for Error {
+    fn from(e: ConfigError) -> Self {
+        Self::Config(e)
+    }
+}
+
+impl Error {
+    /*
+    * Decode a (non-zero) ULD status code.
+    */
+    pub(crate) fn from_uld(st: u8) -> Self {
+        debug_assert!(st != 0);
+
+        match st {
+            ST_TIMEOUT_ERROR => Self::Timeout,
+            ST_CORRUPTED_FRAME => Self::CorruptedFrame,
+            ST_CRC_CSUM_FAILED => Self::CrcCsumFailed,
+            ST_XTALK_FAILED => Self::XtalkFailed,
+            ST_MCU_ERROR => Self::McuError,
+            ST_INVALID_PARAM => Self::InvalidParam,
+            ST_ERROR => Self::Generic,
+            v => Self::Other(v)
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        use Error::*;
+        match self {
+            Timeout => write!(f, "ULD: timeout (1)"),
+            CorruptedFrame => write!(f, "ULD: corrupted frame (2)"),
+            CrcCsumFailed => write!(f, "ULD: CRC checksum failed (3)"),
+            XtalkFailed => write!(f, "ULD: xtalk calibration failed (4)"),
+            McuError => write!(f, "ULD: sensor MCU error (66)"),
+            InvalidParam => write!(f, "ULD: invalid parameter (127)"),
+            Generic => write!(f, "ULD: driver or I2C error (255)"),
+            Other(v) => write!(f, "ULD: combined errors ({})", v),
+            PingMismatch{ dev_id, rev_id } => write!(f, "Unexpected sensor id: ({:#04x}, {:#04x})", dev_id, rev_id),
+            AddrChangeNotConfirmed => write!(f, "Sensor not reached after I2C address change"),
+            PlatformI2c => write!(f, "Platform I2C failure"),
+            Config(e) => write!(f, "Bad config: {}", e),
+            ConfigMismatch => write!(f, "Sensor config differs from the one applied"),
+            NoDataInTime => write!(f, "No new results in time"),
+            Pin => write!(f, "GPIO pin failure"),
+        }
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        use ConfigError::*;
+        match self {
+            FrequencyOutOfRange{ dim, hz, max_hz } => write!(f, "ranging frequency {} Hz out of range for {}x{} (1..={} Hz)", hz, dim, dim, max_hz),
+            IntegrationTimeOutOfRange{ ms } => write!(f, "integration time {} ms out of range (2..=1000 ms)", ms),
+            IntegrationExceedsWindow{ dim, hz, max_ms } => write!(f, "integration window for {}x{} at {} Hz is at most {} ms", dim, dim, hz, max_ms),
+            SharpenerOutOfRange{ prc } => write!(f, "sharpener {}% out of range (1..=99%)", prc),
+            #[cfg(feature = "motion_indicator")]
+            MotionDistanceOutOfRange{ mm } => write!(f, "motion distance {} mm out of range (400..=4000 mm)", mm),
+            #[cfg(feature = "motion_indicator")]
+            MotionWindowEmpty{ min_mm, max_mm } => write!(f, "motion window {}..{} mm is empty", min_mm, max_mm),
+            #[cfg(feature = "motion_indicator")]
+            MotionWindowTooWide{ width_mm } => write!(f, "motion window {} mm wider than 1500 mm", width_mm),
+            #[cfg(feature = "detection_thresholds")]
+            NoThresholds => write!(f, "no thresholds given"),
+            #[cfg(feature = "detection_thresholds")]
+            TooManyThresholds{ max } => write!(f, "too many thresholds (max {})", max),
+            #[cfg(feature = "detection_thresholds")]
+            ThresholdZoneOutOfRange{ zone, zones } => write!(f, "threshold zone {} out of range (0..{})", zone, zones),
+            #[cfg(feature = "xtalk")]
+            XtalkReflectanceOutOfRange{ prc } => write!(f, "xtalk target reflectance {}% out of range (1..=99%)", prc),
+            #[cfg(feature = "xtalk")]
+            XtalkSamplesOutOfRange{ n } => write!(f, "xtalk samples {} out of range (1..=16)", n),
+            #[cfg(feature = "xtalk")]
+            XtalkDistanceOutOfRange{ mm } => write!(f, "xtalk target distance {} mm out of range (600..=3000 mm)", mm),
+        }
+    }
+}
+
+impl Display for CalibrationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        use CalibrationError::*;
+        match self {
+            Size{ n } => write!(f, "calibration blob of {} bytes; expected {}", n, crate::CALIBRATION_SIZE),
+            Magic => write!(f, "not a calibration blob"),
+            Version{ v } => write!(f, "calibration blob version {} not supported", v),
+            Crc => write!(f, "calibration blob CRC mismatch"),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+/*
+* Tracking the sensor's frame counter ("streamcount"), to tell how many frames get lost.
+*
+* The sensor numbers its frames 0..=254 (255 is never used), and wraps. The ULD C code keeps the
+* last number it saw in 'VL53L5CX_Configuration::streamcount': '..._check_data_ready()' sets it
+* when it sees a new frame, and '..._get_ranging_data()' when it reads one.
+*
+* Per frame read, we compare:
+*   - with the previous frame read: a step of 1 is normal; more means frames were made but not
+*       read (a gap); 0 means the same frame was read again (a duplicate)
+*   - with the frame '.is_ready()' saw: if the sensor has made a newer one in between (e.g. the
+*       flock kept the board pending), the read gives the newer frame. The one seen ready is
+*       lost; the read is stale.
+*
+* Note: A gap of exactly 255 frames (a multiple of) is not visible. At 60 Hz, that's ~4s of not
+*       reading.
+*/
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FrameStats {
+    pub frames: u32,        // frames read
+    pub gaps: u32,          // times that frames were skipped
+    pub dropped: u32,       // frames skipped, in total
+    pub duplicates: u32,    // same frame read again
+    pub stale: u32,         // read a newer frame than was seen ready
+    last: Option<u8>,       // counter of the previous frame read
+}
+
+const PERIOD: u16 = 255;    // counter values 0..=254
+
+impl FrameStats {
+    pub const fn new() -> Self {
+        Self{ frames: 0, gaps: 0, dropped: 0, duplicates: 0, stale: 0, last: None }
+    }
+
+    /*
+    * Count a frame read; 'counter' is its streamcount, 'seen' what '.is_ready()' saw (if it was
+    * asked since the previous read).
+    */
+    pub(crate) fn record(&mut self, counter: u8, seen: Option<u8>) {
+        self.frames += 1;
+
+        if let Some(last) = self.last {
+            match (counter as u16 + PERIOD - last as u16) % PERIOD {
+                0 => self.duplicates += 1,
+                1 => {},
+                n => {
+                    self.gaps += 1;
+                    self.dropped += (n - 1) as u32;
+                }
+            }
+        }
+        if seen.is_some_and(|s| s != counter) {
+            self.stale += 1;
+        }
+        self.last = Some(counter);
+    }
+
+    /*
+    * Frames that were read as new ones, and not lost; 0..=1.
+    */
+    pub fn delivery_ratio(&self) -> f32 {
+        let made = self.frames - self.duplicates + self.dropped;
+        if made == 0 { 1.0 } else { (self.frames - self.duplicates) as f32 / made as f32 }
+    }
+}
@@ -0,0 +1,118 @@
+/*
+* 'Platform' implementation over 'embedded-hal' (1.0) traits.
+*
+* Works with any HAL that provides a blocking 'embedded_hal::i2c::I2c' and 'DelayNs'; also with
+* 'embedded-hal-mock', for host side testing.
+*
+*   - ULD writes (up to 32KB, during '.init()') and reads (hundreds of bytes) are chunked so that
+*     a single I2C transaction is at most 'MAX' bytes. The default (254) is what 'esp-hal' manages.
+*   - each write starts with the 2-byte (big endian) register index, in the same transaction
+*   - I2C address changes (via 'State_HP_Idle::set_i2c_address()') are followed
+*
+* I2C errors are given back to the ULD C code (as 255), instead of panicking.
+*/
+#![cfg(feature = "embedded_hal")]
+
+#[cfg(feature = "defmt")]
+use defmt::{error, trace};
+
+use core::result::Result as CoreResult;
+
+use embedded_hal::{
+    delay::DelayNs,
+    i2c::I2c,
+};
+
+use crate::{
+    I2cAddr,
+    Platform,
+    DEFAULT_I2C_ADDR,
+};
+
+pub struct HalPlatform<I2C, D, const MAX: usize = 254> {
+    i2c: I2C,
+    delay: D,
+    i2c_addr: I2cAddr,
+}
+
+impl<I2C: I2c, D: DelayNs, const MAX: usize> HalPlatform<I2C, D, MAX> {
+    /*
+    * The sensor starts at the default I2C address, after each power on.
+    */
+    pub fn new(i2c: I2C, delay: D) -> Self {
+        Self::new_with_addr(i2c, delay, DEFAULT_I2C_ADDR)
+    }
+
+    /*
+    * For a sensor whose address has already been changed (and not lost by a power cycle).
+    */
+    pub fn new_with_addr(i2c: I2C, delay: D, i2c_addr: I2cAddr) -> Self {
+        assert!(MAX > 2, "'MAX' must leave space for the index");
+        Self{ i2c, delay, i2c_addr }
+    }
+
+    // Note: Once handed to 'VL53L5CX::new_with_ping()', the platform stays with the driver.
+    pub fn release(self) -> (I2C, D) {
+        (self.i2c, self.delay)
+    }
+}
+
+impl<I2C: I2c, D: DelayNs, const MAX: usize> Platform for HalPlatform<I2C, D, MAX> {
+    fn rd_bytes(&mut self, index: u16, buf: &mut [u8]) -> CoreResult<(),()> {
+        let addr = self.i2c_addr.as_7bit();
+        let mut index = index;      // rolled further with the chunks
+
+        for chunk in buf.chunks_mut(MAX) {
+            self.i2c.write_read(addr, &index.to_be_bytes(), chunk).map_err(|_e| {
+                #[cfg(feature = "defmt")]
+                error!("I2C read at {:#06x} ({=usize} bytes) failed: {}", index, chunk.len(), defmt::Debug2Format(&_e));
+            })?;
+
+            #[cfg(feature = "defmt")]
+            trace!("I2C read: {:#06x} ({=usize} bytes)", index, chunk.len());
+
+            index = index.wrapping_add(chunk.len() as u16);
+
+            // There should be 1.2ms between transactions, by the VL spec.
+            self.delay.delay_ms(1);
+        }
+        Ok(())
+    }
+
+    fn wr_bytes(&mut self, index: u16, vs: &[u8]) -> CoreResult<(),()> {
+        let addr = self.i2c_addr.as_7bit();
+        let mut index = index;
+
+        // Index and data need to go in a single transaction. Without allocation, we compose
+        // them in a stack buffer.
+        let mut buf = [0_u8; MAX];
+
+        for chunk in vs.chunks(MAX-2) {
+            let n = chunk.len();
+            buf[0..2].copy_from_slice(&index.to_be_bytes());
+            buf[2..2+n].copy_from_slice(chunk);
+
+            self.i2c.write(addr, &buf[..2+n]).map_err(|_e| {
+                #[cfg(feature = "defmt")]
+                error!("I2C write to {:#06x} ({=usize} bytes) failed: {}", index, n, defmt::Debug2Format(&_e));
+            })?;
+
+            #[cfg(feature = "defmt")]
+            trace!("I2C written: {:#06x} ({=usize} bytes)", index, n);
+
+            index = index.wrapping_add(n as u16);
+
+            // There should be 1.3ms between transactions, by the VL spec. (see 'tBUF', p.15)
+            self.delay.delay_ms(1);
+        }
+        Ok(())
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        self.delay.delay_ms(ms);
+    }
+
+    fn addr_changed(&mut self, addr: &I2cAddr) {
+        self.i2c_addr = addr.clone();
+    }
+}
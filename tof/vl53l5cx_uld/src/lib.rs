@@ -1,11 +1,25 @@
 #![no_std]
 #![allow(non_snake_case)]
 
+mod calibration;
+mod detection_thresholds;
+mod error;
+mod frame_stats;
+mod hal_platform;
+mod motion_indicator;
+mod orientation;
+mod persist;
 mod platform;
+mod point_cloud;
 mod state_hp_idle;
+mod state_lp_idle;
 mod state_ranging;
+mod state_ranging_dyn;
 mod results_data;
+mod trace;
 mod uld_raw;
+mod xtalk;
+mod zones;
 pub mod units;
 
 #[cfg(feature = "defmt")]
@@ -13,27 +27,52 @@ use defmt::{debug, error, Format};
 
 use core::{
     ffi::CStr,
-    fmt::{Display, Formatter},
     result::Result as CoreResult,
 };
 
+#[cfg(feature = "motion_indicator")]
+pub use motion_indicator::MotionConfig;
+#[cfg(feature = "detection_thresholds")]
+pub use detection_thresholds::{Combine, Measurement, ThresholdsConfig, Window};
+#[cfg(feature = "xtalk")]
+pub use xtalk::{XtalkData, XTALK_SIZE};
+#[cfg(feature = "embedded_hal")]
+pub use hal_platform::HalPlatform;
+#[cfg(feature = "distance_mm")]
+pub use point_cloud::{Point, PointCloud};
+#[cfg(feature = "persist")]
+pub use persist::{BoardConfig, ConfigRecord, PersistError, RECORD_MAGIC, RECORD_VERSION};
+#[cfg(feature = "trace")]
+pub use trace::{fnv1a, Divergence, RecordingPlatform, ReplayPlatform, TraceOp, TraceSink, TRACE_MAGIC, TRACE_VERSION};
+
 pub use {
+    calibration::{CalibrationBlob, CALIBRATION_MAGIC, CALIBRATION_SIZE, CALIBRATION_VERSION},
+    error::{CalibrationError, ConfigError, Error, Result},
+    frame_stats::FrameStats,
+    orientation::Orientation,
     platform::Platform,
-    results_data::ResultsData,
+    results_data::{Confidence, ResultsData, ResultsDataDyn, TargetStatus},
     state_hp_idle::State_HP_Idle,
+    state_lp_idle::State_LP_Idle,
     state_ranging::{
         Mode,
         RangingConfig,
+        Resolution,
         State_Ranging,
         TargetOrder
-    }
+    },
+    state_ranging_dyn::{
+        RangingConfigDyn,
+        State_Ranging_Dyn,
+    },
+    zones::{Target, Targets, Zone, Zones},
 };
 
 use crate::uld_raw::{
     VL53L5CX_Configuration,
     vl53l5cx_init,
     API_REVISION as API_REVISION_r,   // &[u8] with terminating '\0'
-    ST_OK, ST_ERROR,
+    ST_OK,
 
     /*** tbd. if needed, bring under features
     *vl53l5cx_disable_internal_cp,
@@ -47,18 +86,6 @@ use crate::uld_raw::{
     */
 };
 
-pub type Result<T> = core::result::Result<T,Error>;
-
-#[cfg_attr(feature = "defmt", derive(defmt::Format))]
-#[derive(core::fmt::Debug)]
-pub struct Error(pub u8);
-
-impl Display for Error {
-    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        write!(f, "ULD driver or hardware error ({})", self.0)
-    }
-}
-
 pub const DEFAULT_I2C_ADDR: I2cAddr = I2cAddr::from_8bit(0x52);    // default after each power on
 
 // After LOTS of variations, here's a way to expose a 'CStr' string as a '&str' const (as long as
@@ -86,7 +113,7 @@ impl VL53L5CX_Configuration {
        *
        * Initialized state is (as per ULD C code):
        *   <<
-       *       .platform: dyn Platform     = anything the app keeps there
+       *       .platform: P                = the app's 'Platform', moved in (see 'platform.h')
        *       .streamcount: u8            = 0 (undefined by ULD C code)
        *       .data_read_size: u32        = 0 (undefined by ULD C code)
        *       .default_configuration: *mut u8 = VL53L5CX_DEFAULT_CONFIGURATION (a const table)
@@ -103,15 +130,15 @@ impl VL53L5CX_Configuration {
        *   - default Xtalk data programmed to the sensor
        *   - default configuration ('.default_configuration') written to the sensor
        *   - four bytes written to sensor's DCI memory at '0xDB80U' ('VL53L5CX_DCI_PIPE_CONTROL'):
-       *       {VL53L5CX_NB_TARGET_PER_ZONE, 0x00, 0x01, 0x00}
+       *     {VL53L5CX_NB_TARGET_PER_ZONE, 0x00, 0x01, 0x00}
        *   - if 'NB_TARGET_PER_ZONE' != 1, 1 byte updated at '0x5478+0xc0' ('VL53L5CX_DCI_FW_NB_TARGET'+0xc0)  // if I got that right!?!
-       *       {VL53L5CX_NB_TARGET_PER_ZONE}
+       *     {VL53L5CX_NB_TARGET_PER_ZONE}
        *   - one byte written to sensor's DCI memory at '0xD964' ('VL53L5CX_DCI_SINGLE_RANGE'):
-       *       {0x01}
+       *     {0x01}
        *   - two bytes updated at sensor's DCI memory at '0x0e108' ('VL53L5CX_GLARE_FILTER'):
-       *       {0x01, 0x01}
+       *     {0x01, 0x01}
     */
-    fn init_with<P : Platform + 'static>(mut p: P) -> Result<Self> {
+    fn init_with<P : Platform + 'static>(p: P) -> Result<Self> {
         use core::mem::MaybeUninit;
         use core::ptr::addr_of_mut;
 
@@ -122,33 +149,17 @@ impl VL53L5CX_Configuration {
 
             let up = uninit.as_mut_ptr();
 
-            // Get a '&mut dyn Platform' reference to 'p'. Converting '*c_void' to a templated 'P'
-            // type is beyond the author's imagination (perhaps impossible?) whereas '&mut dyn Platform'
-            // *may be* just within doable!
+            // Move 'p' into 'uninit.platform'; ULD C 'vl.._init()' will pass a pointer to it back
+            // to us (Rust) once platform calls (I2C/delays) are needed.
             //
-            // Nice part of using '&mut dyn Platform' is also that the size and alignment requirements
-            // (16 and 8 bytes), remain constant for the C side.
-            //
-            let dd: &mut dyn Platform = &mut p;
-
-            // Make a bitwise copy of 'dd' in 'uninit.platform'; ULD C 'vl.._init()' will need it,
-            // and pass back to us (Rust) once platform calls (I2C/delays) are needed.
+            // Note: Earlier, we stored a '&mut dyn Platform' to the local 'p', here. That dangled
+            //      as soon as this function returned. Now the 'Platform' travels (by value) with
+            //      the struct, wherever that gets moved.
             //
             // This is what allows multiple VL boards to be utilized, at once; they each will get
             // their own, opaque 'Platform'.
-            {
-                let pp = addr_of_mut!((*up).platform);
-
-                /*** disabled; gives _zero_ for the 'size_of_val(dd)'; WHY????
-                // Check size and alignment
-                let (sz_c,sz_rust) = (field_size!(VL53L5CX_Configuration::platform), size_of_val(dd));
-                assert_eq!(sz_rust,sz_c, "Tunnel entry and exit sizes don't match");   // edit 'platform.h' to adjust
-                ***/
-                let al_rust = align_of_val(dd);
-                assert!( (pp as usize)%al_rust == 0, "bad alignment on C side (needs {})", al_rust );
-
-                *(pp as *mut &mut dyn Platform) = dd;
-            }
+            //
+            platform::install(addr_of_mut!((*up).platform), p);
 
             // Initialize those fields we know C API won't touch (just in case)
             addr_of_mut!((*up).streamcount).write(u8::MAX);
@@ -160,13 +171,25 @@ impl VL53L5CX_Configuration {
             //
             match vl53l5cx_init(up) {
                 0 => Ok(uninit.assume_init()),  // we guarantee it's now initialized
-                e => Err(Error(e))
+                e => {
+                    platform::uninstall(addr_of_mut!((*up).platform));
+                    Err(Error::from_uld(e))
+                }
             }
         };
         ret
     }
 }
 
+/*
+* The 'Platform' within is ours to drop.
+*/
+impl Drop for VL53L5CX_Configuration {
+    fn drop(&mut self) {
+        unsafe { platform::uninstall(&mut self.platform) }
+    }
+}
+
 /**
 * @brief Beginning of preparing access to a single VL53L5CX sensor.
 */
@@ -180,10 +203,8 @@ impl<P: Platform + 'static> VL53L5CX<P> {
     * a suitable sensor out there.
     */
     pub fn new_with_ping(/*move*/ mut p: P) -> Result<Self> {
-        match Self::ping(&mut p) {
-            Err(_) => Err(Error(ST_ERROR)),
-            Ok(()) => Ok(Self{ p })
-        }
+        Self::ping(&mut p)?;
+        Ok(Self{ p })
     }
 
     pub fn init(self) -> Result<State_HP_Idle> {
@@ -192,9 +213,9 @@ impl<P: Platform + 'static> VL53L5CX<P> {
         Ok( State_HP_Idle::new(uld) )
     }
 
-    fn ping(p: &mut P) -> CoreResult<(),()> {
+    fn ping(p: &mut P) -> Result<()> {
         #[cfg_attr(not(feature="defmt"), allow(unused_variables))]
-        match vl53l5cx_ping(p)? {
+        match vl53l5cx_ping(p).map_err(|_| Error::PlatformI2c)? {
             (a@ 0xf0, b@ 0x02) => {     // vendor driver ONLY proceeds with this
                 #[cfg(feature="defmt")]
                 debug!("Ping succeeded: {=u8:#04x},{=u8:#04x}", a,b)
@@ -202,7 +223,7 @@ impl<P: Platform + 'static> VL53L5CX<P> {
             t => {
                 #[cfg(feature="defmt")]
                 error!("Unexpected '(device id, rev id)': {:#04x}", t);
-                return Err(());
+                return Err(Error::PingMismatch{ dev_id: t.0, rev_id: t.1 });
             }
         }
         Ok(())
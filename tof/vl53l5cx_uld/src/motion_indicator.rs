@@ -0,0 +1,102 @@
+/*
+* Motion indicator (vendor plugin)
+*
+* The sensor is able to tell, per zone, how much "motion" it sees within a distance window.
+* This is done by the sensor firmware, comparing consecutive frames, and is thus cheaper (for us)
+* than diffing frames in the application.
+*
+* The plugin needs to be (re)initialized whenever the resolution changes. We do this as part of
+* starting the ranging; the resolution comes from the 'RangingConfig<DIM>' the 'MotionConfig'
+* belongs to.
+*
+* References:
+*   - vendor's UM2884 > "Motion indicator"; Rev 5
+*/
+#![cfg(feature = "motion_indicator")]
+
+use core::mem::MaybeUninit;
+
+use crate::uld_raw::{
+    vl53l5cx_motion_indicator_init,
+    vl53l5cx_motion_indicator_set_distance_motion,
+    Resolution as Resolution_R,
+    VL53L5CX_Configuration,
+    VL53L5CX_Motion_Configuration,
+    ST_OK,
+};
+use crate::{
+    ConfigError,
+    Error,
+    Result,
+};
+
+/*
+* Maps each zone (index in the ULD C API vector) to a motion "aggregate" (index in
+* '.motion_indicator.motion[]'). Negative values: zone not in use.
+*
+* The map is created by the vendor plugin, for the resolution in question. We keep a copy of it
+* for turning the aggregates into a per-zone matrix.
+*/
+pub(crate) type MotionMap = [i8; 64];
+
+#[derive(Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MotionConfig {
+    distance_min_mm: u16,
+    distance_max_mm: u16,
+}
+
+impl MotionConfig {
+    /*
+    * Distance window (mm) within which motion is detected.
+    *
+    * Vendor limits are checked when the ranging starts:
+    *   - both ends within [400..4000]; inclusive
+    *   - window no wider than 1500mm
+    */
+    pub fn new(distance_min_mm: u16, distance_max_mm: u16) -> Self {
+        Self { distance_min_mm, distance_max_mm }
+    }
+
+    pub(crate) fn window(&self) -> (u16, u16) {
+        (self.distance_min_mm, self.distance_max_mm)
+    }
+
+    pub(crate) fn try_validate(&self) -> core::result::Result<(), ConfigError> {
+        let (a,b) = (self.distance_min_mm, self.distance_max_mm);
+
+        for mm in [a,b] {
+            if !(400..=4000).contains(&mm) { return Err(ConfigError::MotionDistanceOutOfRange{ mm }) }
+        }
+        if a >= b { return Err(ConfigError::MotionWindowEmpty{ min_mm: a, max_mm: b }) }
+        if b-a > 1500 { return Err(ConfigError::MotionWindowTooWide{ width_mm: b-a }) }
+        Ok(())
+    }
+
+    /*
+    * Initialize the plugin on the sensor. Must be called after the resolution has been set.
+    */
+    pub(crate) fn apply(&self, vl: &mut VL53L5CX_Configuration, reso: Resolution_R) -> Result<MotionMap> {
+        // Fully initialized by the vendor '..._init()'
+        let mut mc: VL53L5CX_Motion_Configuration = unsafe { MaybeUninit::zeroed().assume_init() };
+
+        match unsafe { vl53l5cx_motion_indicator_init(vl, &mut mc, reso as u8) } {
+            ST_OK => Ok(()),
+            e => Err(Error::from_uld(e))
+        }?;
+
+        match unsafe { vl53l5cx_motion_indicator_set_distance_motion(vl, &mut mc, self.distance_min_mm, self.distance_max_mm) } {
+            ST_OK => Ok(()),
+            e => Err(Error::from_uld(e))
+        }?;
+
+        Ok(mc.map_id)
+    }
+}
+
+impl Default for MotionConfig {
+    // Window used in the vendor examples.
+    fn default() -> Self {
+        Self::new(1000, 2000)
+    }
+}
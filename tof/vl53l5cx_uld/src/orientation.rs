@@ -0,0 +1,55 @@
+/*
+* Mounting orientation of the sensor.
+*
+* 'ResultsData' matrices are as the sensor sees it, with the SATEL board's PCB text horizontal and
+* right-way-up (see 'results_data.rs'). If the board is mounted otherwise, tell it in the
+* 'RangingConfig'; the results are turned back, so that row 0 is again "up" and column 0 "left"
+* (looking out through the sensor).
+*
+* Rotations are clockwise, looking out through the sensor (i.e. as the matrices are). The mirrored
+* variants first rotate, then swap left and right; e.g. for a sensor looking via a mirror.
+*
+* The turning is done while the results are converted from the ULD C API format; it doesn't cost
+* an extra pass.
+*/
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Orientation {
+    #[default]
+    Upright,
+    Rotated90,
+    Rotated180,     // "upside down"
+    Rotated270,
+    Mirrored,
+    MirroredRotated90,
+    MirroredRotated180,
+    MirroredRotated270,
+}
+
+impl Orientation {
+    pub const ALL: [Orientation; 8] = {
+        use Orientation::*;
+        [Upright, Rotated90, Rotated180, Rotated270, Mirrored, MirroredRotated90, MirroredRotated180, MirroredRotated270]
+    };
+
+    /*
+    * For a zone of the (turned) results, '(row, col)' of the same zone as the sensor gives it.
+    */
+    #[inline]
+    pub(crate) fn src<const DIM: usize>(&self, row: usize, col: usize) -> (usize, usize) {
+        use Orientation::*;
+        let last = DIM-1;
+
+        let (r, c) = match self {
+            Upright | Rotated90 | Rotated180 | Rotated270 => (row, col),
+            _ => (row, last - col),     // mirror
+        };
+        match self {
+            Upright | Mirrored => (r, c),
+            Rotated90 | MirroredRotated90 => (last - c, r),
+            Rotated180 | MirroredRotated180 => (last - r, last - c),
+            Rotated270 | MirroredRotated270 => (c, last - r),
+        }
+    }
+}
@@ -0,0 +1,309 @@
+/*
+* Persisting the setup of the boards in flash: per board, the I2C address, ranging config
+* (incl. orientation) and calibration data.
+*
+* At boot, load the record; if it's missing or corrupt, carry on with defaults. Any flash that
+* implements the 'embedded-storage' 'NorFlash' traits does; on the ESP32, that's 'esp-storage'
+* (the 'vl53l5cx' crate wires it).
+*
+* Record format (version 1); all integers little endian:
+*   <<
+*       [0..4]      "VLCF"
+*       [4]         version
+*       [5]         number of boards
+*       [6..8]      length of the boards part (bytes; u16)
+*
+*       per board:
+*           [0]         I2C address (7-bit)
+*           [1]         resolution: 4 | 8
+*           [2..14]     ranging config (see 'CFG_RECORD_SIZE' in 'state_ranging.rs')
+*           [14]        1: calibration blob follows; 0: none
+*           [15]        0 (reserved)
+*           ...         calibration blob (1276 bytes), if any
+*
+*       CRC-32 of all the above
+*       0xff up to the flash's write size
+*   <<
+*
+* The record is stored at a fixed place ('at'; aligned to the flash's erase size) of a region the
+* application reserves: 'ConfigRecord::<N>::MAX_SIZE' bytes, rounded up to the erase size.
+*
+* Migrations: a newer library reading an older record version turns it into the current form
+* (in 'parse()'). There is only version 1, so far. Records of unknown (i.e. newer) versions are
+* not read, but reported.
+*/
+#![cfg(feature = "persist")]
+
+use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+
+use crate::{
+    calibration::{Crc32, CalibrationBlob, CALIBRATION_SIZE},
+    state_ranging::{RangingConfig, CFG_RECORD_SIZE},
+    state_ranging_dyn::RangingConfigDyn,
+    I2cAddr,
+};
+
+pub const RECORD_MAGIC: &[u8; 4] = b"VLCF";
+pub const RECORD_VERSION: u8 = 1;
+
+const HEADER_SIZE: usize = 8;
+const BOARD_SIZE: usize = 16;       // without the calibration
+const CRC_SIZE: usize = 4;
+
+const STAGE: usize = 32;            // flash access chunk; multiple of the flash's read and write sizes
+
+/*
+* Setup of one board.
+*/
+#[derive(Clone)]
+pub struct BoardConfig {
+    pub i2c_addr: I2cAddr,
+    pub cfg: RangingConfigDyn,
+    pub calibration: Option<CalibrationBlob>,
+}
+
+/*
+* Setup of all the 'N' boards; what gets stored.
+*/
+#[derive(Clone)]
+pub struct ConfigRecord<const N: usize> {
+    pub boards: [BoardConfig; N],
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PersistError {
+    Flash,              // the flash driver failed
+    Missing,            // no record (e.g. erased flash)
+    Version{ v: u8 },   // record of a newer library
+    Crc,                // corrupted
+    Boards{ n: u8 },    // record is for a different number of boards
+    BadRecord,          // fields out of range; e.g. a config not passing '.try_validate()', or a length beyond 'MAX_SIZE'
+    #[cfg(feature = "detection_thresholds")]
+    Thresholds{ board: u8 },    // config of 'board' has detection thresholds; they are not stored
+}
+
+impl<const N: usize> ConfigRecord<N> {
+    pub const MAX_SIZE: usize = HEADER_SIZE + N*(BOARD_SIZE + CALIBRATION_SIZE) + CRC_SIZE;
+
+    pub fn load<F: ReadNorFlash>(flash: &mut F, at: u32) -> Result<Self, PersistError> {
+        let mut h = [0_u8; HEADER_SIZE];
+        Reader::new(flash, at).take(&mut h)?;
+
+        if &h[..4] != RECORD_MAGIC { return Err(PersistError::Missing) }
+
+        let (version, n, len) = (h[4], h[5], u16::from_le_bytes([h[6], h[7]]) as usize);
+
+        // Don't trust 'len' (not yet checked) to read past what a record can be
+        if HEADER_SIZE + len + CRC_SIZE > Self::MAX_SIZE { return Err(PersistError::BadRecord) }
+
+        // First pass: is it intact?
+        {
+            let mut rd = Reader::new(flash, at);
+            rd.skip(HEADER_SIZE + len)?;
+            let got = rd.crc.value();
+
+            let mut bs = [0_u8; CRC_SIZE];
+            rd.take(&mut bs)?;
+            if u32::from_le_bytes(bs) != got { return Err(PersistError::Crc) }
+        }
+
+        let mut rd = Reader::new(flash, at);
+        rd.skip(HEADER_SIZE)?;
+        Self::parse(version, n, &mut rd)
+    }
+
+    /*
+    * Load the record or, if that doesn't work out, use 'f'.
+    */
+    pub fn load_or_else<F: ReadNorFlash>(flash: &mut F, at: u32, f: impl FnOnce(PersistError) -> Self) -> Self {
+        Self::load(flash, at).unwrap_or_else(f)
+    }
+
+    /*
+    * Erases the region (as much of it as the record needs) and writes the record.
+    *
+    * Configs with detection thresholds are refused ('PersistError::Thresholds'; nothing is
+    * erased). The record doesn't carry them, and would load without.
+    */
+    pub fn store<F: NorFlash>(&self, flash: &mut F, at: u32) -> Result<(), PersistError> {
+        #[cfg(feature = "detection_thresholds")]
+        if let Some(i) = self.boards.iter().position(|b| match &b.cfg {
+            RangingConfigDyn::_4X4(c) => c.thresholds().is_some(),
+            RangingConfigDyn::_8X8(c) => c.thresholds().is_some(),
+        }) {
+            return Err(PersistError::Thresholds{ board: i as u8 });
+        }
+
+        let len: usize = self.boards.iter()
+            .map(|b| BOARD_SIZE + if b.calibration.is_some() { CALIBRATION_SIZE } else { 0 })
+            .sum();
+
+        let total = HEADER_SIZE + len + CRC_SIZE;
+        let erase_to = at as usize + total.next_multiple_of(F::ERASE_SIZE);
+        flash.erase(at, erase_to as u32).map_err(|_| PersistError::Flash)?;
+
+        let mut wr = Writer::new(flash, at);
+
+        let mut h = [0_u8; HEADER_SIZE];
+        h[..4].copy_from_slice(RECORD_MAGIC);
+        h[4] = RECORD_VERSION;
+        h[5] = N as u8;
+        h[6..8].copy_from_slice(&(len as u16).to_le_bytes());
+        wr.put(&h)?;
+
+        for b in &self.boards {
+            let mut bs = [0_u8; BOARD_SIZE];
+            bs[0] = b.i2c_addr.as_7bit();
+            let (dim, cfg) = match &b.cfg {
+                RangingConfigDyn::_4X4(c) => (4, c.to_record()),
+                RangingConfigDyn::_8X8(c) => (8, c.to_record()),
+            };
+            bs[1] = dim;
+            bs[2..2+CFG_RECORD_SIZE].copy_from_slice(&cfg);
+            bs[14] = b.calibration.is_some() as u8;
+            wr.put(&bs)?;
+
+            if let Some(ref cal) = b.calibration {
+                wr.put(cal.as_bytes())?;
+            }
+        }
+
+        let crc = wr.crc.value();
+        wr.put(&crc.to_le_bytes())?;
+        wr.flush()
+    }
+
+    /*
+    * Read the boards part of a record of 'version', bringing it to the current form.
+    */
+    fn parse<F: ReadNorFlash>(version: u8, n: u8, rd: &mut Reader<F>) -> Result<Self, PersistError> {
+        match version {
+            1 => {},
+            v => return Err(PersistError::Version{ v }),
+        }
+        if n as usize != N { return Err(PersistError::Boards{ n }) }
+
+        let mut err = None;
+        let boards: [Option<BoardConfig>; N] = core::array::from_fn(|_| {
+            if err.is_some() { return None }
+            read_board(rd).map_err(|e| err = Some(e)).ok()
+        });
+
+        match err {
+            Some(e) => Err(e),
+            None => Ok(Self{ boards: boards.map(Option::unwrap) }),
+        }
+    }
+}
+
+fn read_board<F: ReadNorFlash>(rd: &mut Reader<F>) -> Result<BoardConfig, PersistError> {
+    let mut bs = [0_u8; BOARD_SIZE];
+    rd.take(&mut bs)?;
+
+    if bs[0] >= 0x80 { return Err(PersistError::BadRecord) }
+    let i2c_addr = I2cAddr::from_7bit(bs[0]);
+
+    let cfg_bs: &[u8; CFG_RECORD_SIZE] = bs[2..2+CFG_RECORD_SIZE].try_into().unwrap();
+    let cfg: RangingConfigDyn = match bs[1] {
+        4 => RangingConfig::<4>::from_record(cfg_bs).map(Into::into),
+        8 => RangingConfig::<8>::from_record(cfg_bs).map(Into::into),
+        _ => None
+    }.ok_or(PersistError::BadRecord)?;
+
+    cfg.try_validate().map_err(|_| PersistError::BadRecord)?;
+
+    let calibration = match bs[14] {
+        0 => None,
+        1 => {
+            let mut cal = [0_u8; CALIBRATION_SIZE];
+            rd.take(&mut cal)?;
+            Some( CalibrationBlob::try_from(&cal[..]).map_err(|_| PersistError::BadRecord)? )
+        },
+        _ => return Err(PersistError::BadRecord)
+    };
+
+    Ok(BoardConfig{ i2c_addr, cfg, calibration })
+}
+
+/*
+* Sequential reads, in aligned 'STAGE' chunks. Keeps a CRC of what's been taken.
+*/
+struct Reader<'a, F: ReadNorFlash> {
+    flash: &'a mut F,
+    at: u32,            // next chunk
+    buf: [u8; STAGE],
+    i: usize,           // next byte in 'buf'
+    crc: Crc32,
+}
+
+impl<'a, F: ReadNorFlash> Reader<'a, F> {
+    fn new(flash: &'a mut F, at: u32) -> Self {
+        assert!(STAGE % F::READ_SIZE == 0 && at as usize % F::READ_SIZE == 0);
+        Self{ flash, at, buf: [0; STAGE], i: STAGE, crc: Crc32::new() }
+    }
+
+    fn take(&mut self, out: &mut [u8]) -> Result<(), PersistError> {
+        for b in out.iter_mut() {
+            if self.i == STAGE {
+                self.flash.read(self.at, &mut self.buf).map_err(|_| PersistError::Flash)?;
+                self.at += STAGE as u32;
+                self.i = 0;
+            }
+            *b = self.buf[self.i];
+            self.i += 1;
+        }
+        self.crc.update(out);
+        Ok(())
+    }
+
+    fn skip(&mut self, mut n: usize) -> Result<(), PersistError> {
+        let mut tmp = [0_u8; STAGE];
+        while n > 0 {
+            let k = n.min(STAGE);
+            self.take(&mut tmp[..k])?;
+            n -= k;
+        }
+        Ok(())
+    }
+}
+
+/*
+* Sequential writes, in 'STAGE' chunks (the last one padded to the flash's write size).
+*/
+struct Writer<'a, F: NorFlash> {
+    flash: &'a mut F,
+    at: u32,
+    buf: [u8; STAGE],
+    n: usize,
+    crc: Crc32,
+}
+
+impl<'a, F: NorFlash> Writer<'a, F> {
+    fn new(flash: &'a mut F, at: u32) -> Self {
+        assert!(STAGE % F::WRITE_SIZE == 0 && at as usize % F::ERASE_SIZE == 0);
+        Self{ flash, at, buf: [0; STAGE], n: 0, crc: Crc32::new() }
+    }
+
+    fn put(&mut self, bs: &[u8]) -> Result<(), PersistError> {
+        self.crc.update(bs);
+        for b in bs {
+            self.buf[self.n] = *b;
+            self.n += 1;
+            if self.n == STAGE { self.flush()? }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), PersistError> {
+        if self.n == 0 { return Ok(()) }
+
+        let len = self.n.next_multiple_of(F::WRITE_SIZE);
+        self.buf[self.n..len].fill(0xff);
+        self.flash.write(self.at, &self.buf[..len]).map_err(|_| PersistError::Flash)?;
+
+        self.at += len as u32;
+        self.n = 0;
+        Ok(())
+    }
+}
@@ -8,7 +8,8 @@
 use defmt::{trace, warn};
 
 use core::{
-    ffi::c_void,
+    mem::{align_of, size_of, MaybeUninit},
+    ptr::{self, addr_of_mut},
     slice,
     result::Result as CoreResult,
 };
@@ -40,7 +41,9 @@ pub trait Platform {
     //          - bool              | just feels... wrong in Rust
     //          - Option            | nah
     //
+    #[allow(clippy::result_unit_err)]
     fn rd_bytes(&mut self, index: u16, buf: &mut [u8]) -> CoreResult<(),()>;
+    #[allow(clippy::result_unit_err)]
     fn wr_bytes(&mut self, index: u16, vs: &[u8]) -> CoreResult<(),()>;
     fn delay_ms(&mut self, ms: u32);
 
@@ -150,13 +153,13 @@ pub extern "C" fn VL53L5CX_SwapBuffer(buf: *mut u8, size: u16 /*size in bytes; n
     // Note: Since we don't actually _know_, whether 'buffer' is 4-byte aligned (to be used as '*mut u32'),
     // The original doc mentions a blurry "generally uint32_t" (not very helpful).
     //
-    assert!(buf as usize %4 <= 0, "Buffer to swap byte order not 'u32' aligned");   // '<= 0' to avoid an IDE warning
+    assert!(buf as usize %4 == 0, "Buffer to swap byte order not 'u32' aligned");
 
     let words: usize = (size as usize)/4;
     let s: &mut[u32] = unsafe { slice::from_raw_parts_mut(buf as *mut u32, words) };
 
-    for i in 0..words {
-        s[i] = u32::swap_bytes(s[i])
+    for w in s.iter_mut() {
+        *w = u32::swap_bytes(*w)
     }
 }
 
@@ -174,6 +177,50 @@ pub extern "C" fn VL53L5CX_WaitMs(pt: *mut VL53L5CX_Platform, time_ms: u32) -> u
     })
 }
 
+/*
+* The 'VL53L5CX_Platform' slot (see 'platform.h'), as seen from Rust.
+*
+* The app's 'Platform' lives (by value) in 'payload'. The two functions are monomorphized for
+* its type, at '.install()', so that later access doesn't need to know the type.
+*/
+const PAYLOAD: usize = 64;      // 'VL53L5CX_PLATFORM_PAYLOAD'
+
+#[repr(C)]
+struct Slot {
+    cast: unsafe fn(*mut u8) -> *mut dyn Platform,
+    drop: unsafe fn(*mut u8),
+    payload: [MaybeUninit<u64>; PAYLOAD/8],     // 8 aligned
+}
+
+const _: () = assert!(size_of::<Slot>() == size_of::<VL53L5CX_Platform>(), "'platform.h' and 'Slot' disagree");
+
+/*
+* Move 'p' into the slot. The slot is expected to be uninitialized.
+*/
+pub(crate)
+fn install<P: Platform + 'static>(pt: *mut VL53L5CX_Platform, p: P) {
+    const { assert!(size_of::<P>() <= PAYLOAD && align_of::<P>() <= 8, "'Platform' does not fit 'VL53L5CX_PLATFORM_PAYLOAD'") };
+
+    unsafe fn cast<P: Platform + 'static>(x: *mut u8) -> *mut dyn Platform { x as *mut P }
+    unsafe fn drop<P>(x: *mut u8) { ptr::drop_in_place(x as *mut P) }
+
+    let s = pt as *mut Slot;
+    unsafe {
+        addr_of_mut!((*s).cast).write(cast::<P>);
+        addr_of_mut!((*s).drop).write(drop::<P>);
+        (addr_of_mut!((*s).payload) as *mut P).write(p);
+    }
+}
+
+/*
+* Drop the 'Platform' in the slot. The slot must not be used, after this.
+*/
+pub(crate)
+unsafe fn uninstall(pt: *mut VL53L5CX_Platform) {
+    let s = pt as *mut Slot;
+    ((*s).drop)(addr_of_mut!((*s).payload) as *mut u8)
+}
+
 /*
 * NOTE! *FINALLY* thinking like Rust!! Using 'with' and a closure, we don't need to worry about
 *   lifespans of the converted pointer!
@@ -181,18 +228,10 @@ pub extern "C" fn VL53L5CX_WaitMs(pt: *mut VL53L5CX_Platform, time_ms: u32) -> u
 pub(crate)  // open for 'set_i2c_address()' so that the I2C address can be changed, on the fly!!!
 fn with<T, F: Fn(&mut dyn Platform) -> T>(pt: *mut VL53L5CX_Platform, f: F) -> T {
 
-    let x: &mut dyn Platform = {    // re-interpret what's in '*pt' as '&mut dyn Platform'
-        let pt: *mut &mut dyn Platform = pt as *mut c_void as _;
-        unsafe{ *pt }
-    };
-
-    /*** Something we didn't try:
-    let pt = unsafe {
-        core::ptr::NonNull::new_unchecked(pt).cast::<&mut dyn Platform>().as_ptr()
+    let x: &mut dyn Platform = unsafe {     // reach the 'Platform' in '*pt'
+        let s = pt as *mut Slot;
+        &mut *((*s).cast)(addr_of_mut!((*s).payload) as *mut u8)
     };
-    unsafe { *pt }
-    ***/
 
     f(x)
 }
-
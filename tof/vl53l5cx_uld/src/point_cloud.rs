@@ -0,0 +1,156 @@
+/*
+* Results as 3D points.
+*
+* Each zone looks out along its own ray. The sensor's field of view is 45° x 45° (63° diagonal),
+* split evenly (by angle) between the zones; a zone's ray goes through the middle of it. The
+* distance of a target is taken to be along that ray.
+*
+* Coordinates are in mm, in the sensor's frame (like a camera's "optical" frame):
+*   - x: to the right
+*   - y: down
+*   - z: out of the sensor
+*
+*   "right" and "down" are as in 'ResultsData', looking out through the sensor: row 0 is the top,
+*   column 0 the left.
+*
+* Note: 'f32' because that's what the consumers (mapping, obstacle avoidance) use. The MCUs we
+*       target do software floats; 'DIM*DIM*TARGETS' points per frame is fine for them.
+*/
+#![cfg(feature = "distance_mm")]
+
+use crate::results_data::{
+    ResultsData,
+    TARGETS,
+};
+#[cfg(feature = "target_status")]
+use crate::results_data::TargetStatus;
+
+/*
+* 'tan()' of the zone ray angles (middle of each zone), from the center outwards. The FoV is
+* 45°; zones are '45°/DIM' wide.
+*
+* Note: 'core' doesn't have trigonometry (it's in 'std' / 'libm'), so these are precalculated.
+*/
+const TAN_4: [f32; 2] = [0.098_491_4, 0.303_346_7];                         // 5.625°, 16.875°
+const TAN_8: [f32; 4] = [0.049_126_8, 0.148_336, 0.250_487, 0.357_805_7];       // 2.8125°, ..19.6875°
+
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    #[cfg(feature = "target_status")]
+    pub status: TargetStatus,
+}
+
+impl Point {
+    const ORIGIN: Point = Point{
+        x: 0.0, y: 0.0, z: 0.0,
+        #[cfg(feature = "target_status")]
+        status: TargetStatus::NoTarget,
+    };
+
+    #[cfg(feature = "target_status")]
+    pub fn is_valid(&self) -> bool {
+        self.status.is_valid()
+    }
+}
+
+/*
+* Points in the same layout as 'ResultsData' fields: '[target][row][col]'.
+*
+* Zones where a target doesn't exist have 'distance_mm' 0, i.e. the point is at the origin. Its
+* 'status' tells.
+*/
+#[derive(Clone, Debug)]
+pub struct PointCloud<const DIM: usize> {
+    pub points: [[[Point; DIM]; DIM]; TARGETS],
+}
+
+impl<const DIM: usize> PointCloud<DIM> {
+    /*
+    * Provide an empty buffer; fed by 'ResultsData::point_cloud_into()'.
+    */
+    pub fn empty() -> Self {
+        Self{ points: [[[Point::ORIGIN; DIM]; DIM]; TARGETS] }
+    }
+
+    /*
+    * All valid points (as '(row, col, point)').
+    */
+    #[cfg(feature = "target_status")]
+    pub fn valid_points(&self) -> impl Iterator<Item = (usize, usize, &Point)> + '_ {
+        self.points.iter().flat_map(|m| {
+            m.iter().enumerate().flat_map(|(row, r)| {
+                r.iter().enumerate().map(move |(col, p)| (row, col, p))
+            })
+        }).filter(|(_, _, p)| p.is_valid())
+    }
+}
+
+impl<const DIM: usize> ResultsData<DIM> {
+    pub fn point_cloud(&self) -> PointCloud<DIM> {
+        let mut pc = PointCloud::empty();
+        self.point_cloud_into(&mut pc);
+        pc
+    }
+
+    /*
+    * Like 'point_cloud()', but into a buffer the application keeps.
+    */
+    pub fn point_cloud_into(&self, out: &mut PointCloud<DIM>) {
+        for row in 0..DIM {
+            for col in 0..DIM {
+                let (dx, dy, dz) = ray::<DIM>(row, col);
+
+                for i in 0..TARGETS {
+                    let d = self.distance_mm[i][row][col] as f32;
+
+                    out.points[i][row][col] = Point{
+                        x: d * dx,
+                        y: d * dy,
+                        z: d * dz,
+                        #[cfg(feature = "target_status")]
+                        status: self.target_status[i][row][col],
+                    };
+                }
+            }
+        }
+    }
+}
+
+/*
+* Unit vector of a zone's ray.
+*/
+fn ray<const DIM: usize>(row: usize, col: usize) -> (f32, f32, f32) {
+    let tx = tan_at::<DIM>(col);
+    let ty = tan_at::<DIM>(row);
+
+    // (tx, ty, 1), normalized
+    let k = inv_sqrt(1.0 + tx*tx + ty*ty);
+    (tx * k, ty * k, k)
+}
+
+// 'tan()' of the angle of the i'th zone (column or row); negative for the left (upper) half.
+fn tan_at<const DIM: usize>(i: usize) -> f32 {
+    let tans: &[f32] = match DIM {
+        4 => &TAN_4,
+        8 => &TAN_8,
+        _ => unreachable!()
+    };
+    let h = DIM/2;
+    if i < h { -tans[h-1-i] } else { tans[i-h] }
+}
+
+/*
+* '1/sqrt(x)', for x in [1, 2); 'core' doesn't have 'sqrt()'. Newton's method from a linear
+* first guess; three rounds are plenty for 'f32'.
+*/
+fn inv_sqrt(x: f32) -> f32 {
+    let mut y = 1.0 - 0.3 * (x - 1.0);
+    for _ in 0..3 {
+        y = y * (1.5 - 0.5 * x * y * y);
+    }
+    y
+}
@@ -23,9 +23,13 @@ use crate::uld_raw::{
     VL53L5CX_ResultsData,
 };
 use crate::units::TempC;
+use crate::orientation::Orientation;
+use crate::state_ranging::Resolution;
+#[cfg(feature = "motion_indicator")]
+use crate::motion_indicator::MotionMap;
 
 // Note: We could also take in 'TARGETS_PER_ZONE' from the ULD C API wrapper.
-const TARGETS: usize =
+pub(crate) const TARGETS: usize =
          if cfg!(feature = "targets_per_zone_4") { 4 }
     else if cfg!(feature = "targets_per_zone_3") { 3 }
     else if cfg!(feature = "targets_per_zone_2") { 2 }
@@ -49,6 +53,8 @@ pub struct ResultsData<const DIM: usize> {      // DIM: 4,8
     pub spads_enabled: [[u32; DIM]; DIM],
     #[cfg(feature = "nb_targets_detected")]
     pub targets_detected: [[u8; DIM]; DIM],     // 1..{X in 'targets_per_zone_X' feature}
+    #[cfg(feature = "motion_indicator")]
+    pub motion: [[u32; DIM]; DIM],              // motion intensity; zones sharing an aggregate have the same value
 
     // Actual results: DIMxDIMxTARGETS
     #[cfg(feature = "target_status")]
@@ -67,9 +73,10 @@ pub struct ResultsData<const DIM: usize> {      // DIM: 4,8
 
 impl<const DIM: usize> ResultsData<DIM> {
     /*
-    * Provide an empty buffer-like struct; owned usually by the application and fed via 'feed()'.
+    * Provide an empty buffer-like struct; owned usually by the application and fed via
+    * 'State_Ranging::get_data_into()'.
     */
-    fn empty() -> Self {
+    pub fn empty() -> Self {
 
         Self {
             #[cfg(feature = "ambient_per_spad")]
@@ -78,6 +85,8 @@ impl<const DIM: usize> ResultsData<DIM> {
             spads_enabled: [[0;DIM];DIM],
             #[cfg(feature = "nb_targets_detected")]
             targets_detected: [[0;DIM];DIM],
+            #[cfg(feature = "motion_indicator")]
+            motion: [[0;DIM];DIM],
 
             #[cfg(feature = "target_status")]
             target_status: [[[TargetStatus::NoTarget;DIM];DIM];TARGETS],
@@ -94,16 +103,13 @@ impl<const DIM: usize> ResultsData<DIM> {
         }
     }
 
-    pub(crate) fn from(raw_results: &VL53L5CX_ResultsData) -> (Self,TempC) {
-        //validate_raw(raw_results);  // panics if input not according to expectations
-
-        // tbd. Implement using 'MaybeUninit'; started but left..wasn't as easy as hoped.
-        let mut x = Self::empty();
-        let tempC = x.feed(raw_results);
-        (x, tempC)
-    }
-
-    fn feed(&mut self, rr: &VL53L5CX_ResultsData) -> TempC {
+    /*
+    * Overwrites all the (enabled) fields; a buffer can be fed again and again.
+    *
+    * 'o' turns the matrices, for how the sensor is mounted (see 'orientation.rs').
+    */
+    pub(crate) fn feed(&mut self, rr: &VL53L5CX_ResultsData, o: Orientation) -> TempC {
+        //validate_raw(rr);  // panics if input not according to expectations
 
         // helpers
         //
@@ -120,6 +126,9 @@ impl<const DIM: usize> ResultsData<DIM> {
         // ULD C API vector:
         //      [A₁ A₂ B₁ B₂ C₁ C₂ D₁ D₂]   // every "zone" is first covered; then next zone
         //
+        // With an 'Orientation' other than 'Upright', 'out[r][c]' is picked from the zone 'o.src(r,c)'
+        // instead.
+        //
         // Rust note:
         //      'const DIM' generic needs to be repeated for each 'fn'; we cannot use the "outer":
         //          <<
@@ -127,28 +136,30 @@ impl<const DIM: usize> ResultsData<DIM> {
         //          <<
         //
         #[allow(dead_code)]
-        fn into_matrix_map_o<IN: Copy, OUT, const DIM: usize>(raw: &[IN], offset: usize, out: &mut [[OUT; DIM]; DIM], f: impl Fn(IN) -> OUT) {
+        fn into_matrix_map_o<IN: Copy, OUT, const DIM: usize>(raw: &[IN], o: Orientation, offset: usize, out: &mut [[OUT; DIM]; DIM], f: impl Fn(IN) -> OUT) {
             let raw = &raw[..DIM * DIM * TARGETS];      // take only the beginning of the C buffer
 
-            for r in 0..DIM {
-                for c in 0..DIM {
-                    out[r][c] = f(raw[(r * DIM + c) * TARGETS + offset]);
+            for (r, row) in out.iter_mut().enumerate() {
+                for (c, x) in row.iter_mut().enumerate() {
+                    let (sr, sc) = o.src::<DIM>(r, c);
+                    *x = f(raw[(sr * DIM + sc) * TARGETS + offset]);
                 }
             }
         }
         #[inline]
         #[allow(dead_code)]
-        fn into_matrix_o<X: Copy, const DIM: usize>(raw: &[X], offset: usize, out: &mut [[X; DIM]; DIM]) {     // no mapping
-            into_matrix_map_o(raw, offset, out, identity)
+        fn into_matrix_o<X: Copy, const DIM: usize>(raw: &[X], o: Orientation, offset: usize, out: &mut [[X; DIM]; DIM]) {     // no mapping
+            into_matrix_map_o(raw, o, offset, out, identity)
         }
         // Zone metadata: 'TARGETS' (and 'offset', by extension) are not involved.
         #[allow(dead_code)]
-        fn into_matrix<X: Copy, const DIM: usize>(raw: &[X], out: &mut [[X; DIM]; DIM]) {
+        fn into_matrix<X: Copy, const DIM: usize>(raw: &[X], o: Orientation, out: &mut [[X; DIM]; DIM]) {
             let raw = &raw[..DIM * DIM];      // take only the beginning of the C buffer
 
-            for r in 0..DIM {
-                for c in 0..DIM {
-                    out[r][c] = raw[r*DIM+c];
+            for (r, row) in out.iter_mut().enumerate() {
+                for (c, x) in row.iter_mut().enumerate() {
+                    let (sr, sc) = o.src::<DIM>(r, c);
+                    *x = raw[sr*DIM+sc];
                 }
             }
         }
@@ -156,77 +167,191 @@ impl<const DIM: usize> ResultsData<DIM> {
         // Metadata: DIMxDIM (just once)
         //
         #[cfg(feature = "ambient_per_spad")]
-        into_matrix(&rr.ambient_per_spad, &mut self.ambient_per_spad);
+        into_matrix(&rr.ambient_per_spad, o, &mut self.ambient_per_spad);
         #[cfg(feature = "nb_spads_enabled")]
-        into_matrix(&rr.nb_spads_enabled, &mut self.spads_enabled);
+        into_matrix(&rr.nb_spads_enabled, o, &mut self.spads_enabled);
         #[cfg(feature = "nb_targets_detected")]
-        into_matrix(&rr.nb_target_detected, &mut self.targets_detected);
+        into_matrix(&rr.nb_target_detected, o, &mut self.targets_detected);
 
         // Results: DIMxDIMxTARGETS
         //
         for i in 0..TARGETS {
             #[cfg(feature = "target_status")]
-            into_matrix_map_o(&rr.target_status, i, &mut self.target_status[i], TargetStatus::from_uld);
+            into_matrix_map_o(&rr.target_status, o, i, &mut self.target_status[i], TargetStatus::from_uld);
 
             // We tolerate '.distance_mm' == 0 for non-existing data (where '.target_status' is 0); no need to check.
             //
             #[cfg(feature = "distance_mm")]
-            into_matrix_map_o(&rr.distance_mm, i, &mut self.distance_mm[i],
+            into_matrix_map_o(&rr.distance_mm, o, i, &mut self.distance_mm[i],
             |v: i16| -> u16 {
                 assert!(v >= 0, "Unexpected 'distance_mm' value: {} < 0", v); v as u16
             });
             #[cfg(feature = "range_sigma_mm")]
-            into_matrix_o(&rr.range_sigma_mm, i, &mut self.range_sigma_mm[i]);
+            into_matrix_o(&rr.range_sigma_mm, o, i, &mut self.range_sigma_mm[i]);
 
             #[cfg(feature = "reflectance_percent")]
-            into_matrix_o(&rr.reflectance, i, &mut self.reflectance[i]);
+            into_matrix_o(&rr.reflectance, o, i, &mut self.reflectance[i]);
             #[cfg(feature = "signal_per_spad")]
-            into_matrix_o(&rr.signal_per_spad, i, &mut self.signal_per_spad[i]);
+            into_matrix_o(&rr.signal_per_spad, o, i, &mut self.signal_per_spad[i]);
         }
 
         TempC(rr.silicon_temp_degc)
     }
+
+    /*
+    * The motion indicator provides its values per "aggregate" (max 32), not per zone. The
+    * 'map' (from the plugin configuration) tells, which aggregate each zone belongs to.
+    */
+    #[cfg(feature = "motion_indicator")]
+    pub(crate) fn feed_motion(&mut self, rr: &VL53L5CX_ResultsData, map: &MotionMap, o: Orientation) {
+        let aggr = &rr.motion_indicator.motion;
+
+        for r in 0..DIM {
+            for c in 0..DIM {
+                let (sr, sc) = o.src::<DIM>(r, c);
+                self.motion[r][c] = match map[sr*DIM+sc] {
+                    i if i >= 0 && (i as usize) < aggr.len() => aggr[i as usize],
+                    _ => 0
+                };
+            }
+        }
+    }
+}
+
+/*
+* Results data when the resolution is only known at runtime (see 'State_Ranging_Dyn').
+*
+* Note: Size is that of the 8x8 variant, also when carrying 4x4 data.
+*/
+#[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]    // on purpose (above); one buffer fits both resolutions
+pub enum ResultsDataDyn {
+    _4X4(ResultsData<4>),
+    _8X8(ResultsData<8>),
+}
+
+impl ResultsDataDyn {
+    pub fn empty(reso: Resolution) -> Self {
+        match reso {
+            Resolution::_4X4 => Self::_4X4(ResultsData::empty()),
+            Resolution::_8X8 => Self::_8X8(ResultsData::empty()),
+        }
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        match self {
+            Self::_4X4(_) => Resolution::_4X4,
+            Self::_8X8(_) => Resolution::_8X8,
+        }
+    }
 }
 
 //---
 // Target status
 //
 // Note: Vendor docs (UM2884 Rev.5; chapter 5.5; Table 4) gives detailed explanations for values
-//      0..13 and 255. We intend to provide enums for values that are _actually seen_, so that
-//      application code doesn't need to deal with integers. Where multiple choices exist, they
-//      are provided  as the inner values.
+//      0..13 and 255. Each has its own variant, so that application code doesn't need to deal
+//      with integers. Values outside the table (not expected) come as 'Unknown'.
+//
+//      UM2884 also gives a validity for the statuses: 5 is 100% valid, 6 and 9 are 50% valid,
+//      others below 50%. That's what 'confidence()' tells.
 //
-#[derive(Copy, Clone, Debug)]       // 'Clone' needed for 'ResultsData' to be cloneable.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]       // 'Clone' needed for 'ResultsData' to be cloneable.
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum TargetStatus {
-    NotUpdated,         // 0    "Ranging data are not updated" (O)
-    Valid,              // 5    "Range valid" = 100% valid
-    SemiValid(u8),      // 6    "Wrap around not performed (typically the first range)"
-                        // 9    "Range valid with large pulse (may be due to a merged target)"
-    NoTarget,           // 255  "No target detected (only if number of targets detected is enabled)"
-    Error(u8),          // 1    "Signal rate too slow on SPAD array"
-                        // 2    "Target phase"
-                        // 3    "Sigma estimator too high"
-                        // 4    "Target consistency failed" (O)
-                        // 7    "Rate consistency failed"
-                        // 8    "Signal rate too low for the current target"
-                        // 10   "Range valid, but no target detected at previous range"
-                        // 11   "Measurement consistency failed"
-                        // 12   "Target blurred by another one, due to sharpener"
-                        // 13   "Target detected but inconsistent data. Frequently happens for secondary targets." (O)
-                        //
-                        //      (O): Observed in wild
+    NotUpdated,                 // 0    "Ranging data are not updated" (O)
+    SignalRateTooLowSpad,       // 1    "Signal rate too slow on SPAD array"
+    TargetPhase,                // 2    "Target phase"
+    SigmaTooHigh,               // 3    "Sigma estimator too high"
+    TargetConsistencyFailed,    // 4    "Target consistency failed" (O)
+    Valid,                      // 5    "Range valid"
+    WrapAroundNotPerformed,     // 6    "Wrap around not performed (typically the first range)" (O)
+    RateConsistencyFailed,      // 7    "Rate consistency failed"
+    SignalRateTooLow,           // 8    "Signal rate too low for the current target"
+    ValidLargePulse,            // 9    "Range valid with large pulse (may be due to a merged target)" (O)
+    NoPreviousTarget,           // 10   "Range valid, but no target detected at previous range"
+    MeasurementConsistencyFailed,   // 11   "Measurement consistency failed"
+    BlurredBySharpener,         // 12   "Target blurred by another one, due to sharpener"
+    InconsistentData,           // 13   "Target detected but inconsistent data. Frequently happens for secondary targets." (O)
+    NoTarget,                   // 255  "No target detected (only if number of targets detected is enabled)"
+    Unknown(u8),                // anything else
+                                //
+                                //      (O): Observed in wild
+}
+
+/*
+* How much a target with a certain status can be trusted; by UM2884.
+*
+* Ordered, so 'st.confidence() >= Confidence::Half' works.
+*/
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Confidence {
+    NoRange,    // not updated, no target, unknown status
+    Low,        // < 50%
+    Half,       // 50%
+    Full,       // 100%
 }
 
 impl TargetStatus {
     fn from_uld(v: u8) -> Self {
         match v {
-            0 => { Self::NotUpdated }
-            5 => { Self::Valid },
-            6 | 9 => { Self::SemiValid(v) },
-            255 => { Self::NoTarget },
-            ..=13 => { Self::Error(v) },
-            v => panic!("Unexpected value {} for target status", v),
+            0 => Self::NotUpdated,
+            1 => Self::SignalRateTooLowSpad,
+            2 => Self::TargetPhase,
+            3 => Self::SigmaTooHigh,
+            4 => Self::TargetConsistencyFailed,
+            5 => Self::Valid,
+            6 => Self::WrapAroundNotPerformed,
+            7 => Self::RateConsistencyFailed,
+            8 => Self::SignalRateTooLow,
+            9 => Self::ValidLargePulse,
+            10 => Self::NoPreviousTarget,
+            11 => Self::MeasurementConsistencyFailed,
+            12 => Self::BlurredBySharpener,
+            13 => Self::InconsistentData,
+            255 => Self::NoTarget,
+            v => Self::Unknown(v),
+        }
+    }
+
+    /*
+    * The raw value, as in UM2884 Table 4.
+    */
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            Self::NotUpdated => 0,
+            Self::SignalRateTooLowSpad => 1,
+            Self::TargetPhase => 2,
+            Self::SigmaTooHigh => 3,
+            Self::TargetConsistencyFailed => 4,
+            Self::Valid => 5,
+            Self::WrapAroundNotPerformed => 6,
+            Self::RateConsistencyFailed => 7,
+            Self::SignalRateTooLow => 8,
+            Self::ValidLargePulse => 9,
+            Self::NoPreviousTarget => 10,
+            Self::MeasurementConsistencyFailed => 11,
+            Self::BlurredBySharpener => 12,
+            Self::InconsistentData => 13,
+            Self::NoTarget => 255,
+            Self::Unknown(v) => *v,
+        }
+    }
+
+    /*
+    * 100% valid range (status 5).
+    */
+    pub fn is_valid(&self) -> bool {
+        *self == Self::Valid
+    }
+
+    pub fn confidence(&self) -> Confidence {
+        match self {
+            Self::Valid => Confidence::Full,
+            Self::WrapAroundNotPerformed | Self::ValidLargePulse => Confidence::Half,
+            Self::NotUpdated | Self::NoTarget | Self::Unknown(_) => Confidence::NoRange,
+            _ => Confidence::Low,
         }
     }
 }
@@ -5,7 +5,7 @@
 *
 *   - HP Idle   // allows transition to the other two
 *   - Ranging
-*   - LP Idle   // we don't (currently) support this mode
+*   - LP Idle   // low power; firmware and I2C address retained
 *
 * The larger point is that the Rust API reflects the states. You can have the sensor presented
 * as 'SensorReady', but if you transit to ranging, you no longer have access to that state (unless
@@ -18,10 +18,15 @@ use defmt::error;
 
 use crate::{
     platform,
+    state_lp_idle::State_LP_Idle,
     state_ranging::{
         RangingConfig,
         State_Ranging,
     },
+    state_ranging_dyn::{
+        RangingConfigDyn,
+        State_Ranging_Dyn,
+    },
     uld_raw::{
         vl53l5cx_get_power_mode,
         VL53L5CX_Configuration
@@ -52,6 +57,13 @@ pub struct State_HP_Idle {
     uld: VL53L5CX_Configuration,
 }
 
+// Only the name; the ULD memory isn't for showing. Lets the '(state, Error)' results be '.unwrap()'ed.
+impl core::fmt::Debug for State_HP_Idle {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str("State_HP_Idle")
+    }
+}
+
 impl State_HP_Idle {
     pub(crate) fn new(uld: VL53L5CX_Configuration) -> Self {
         Self{ uld }
@@ -65,6 +77,45 @@ impl State_HP_Idle {
         Ok(r)
     }
 
+    /*
+    * Like '.start_ranging()', but with the resolution decided at runtime.
+    */
+    pub fn start_ranging_dyn(/*move*/ self, cfg: &RangingConfigDyn) -> Result<State_Ranging_Dyn> {
+        State_Ranging_Dyn::transition_from(self, cfg)
+    }
+
+    /*
+    * What the sensor is set to, as it tells it (see 'RangingConfig::read_from()'). E.g. to confirm
+    * the config survived a brown-out, or a partially failed '.start_ranging()'.
+    */
+    pub fn read_config<const DIM: usize>(&mut self) -> Result<RangingConfig<DIM>> {
+        RangingConfig::read_from(&mut self.uld)
+    }
+
+    /*
+    * Check that the sensor runs 'cfg' (as far as it can be read back); 'Error::ConfigMismatch'
+    * if not.
+    */
+    pub fn verify_config<const DIM: usize>(&mut self, cfg: &RangingConfig<DIM>) -> Result<()> {
+        let got = self.read_config::<DIM>()?;
+
+        if cfg.same_on_sensor(&got) { Ok(()) } else { Err(Error::ConfigMismatch) }
+    }
+
+    //---
+    // Low power
+    //
+    /*
+    * Park the sensor in low power mode ("LP Idle"). Firmware, configuration and the I2C address
+    * are kept; use '.wake_up()' on the returned state to get back here.
+    *
+    * On failure, this state comes back with the error (see 'state_lp_idle.rs').
+    */
+    #[allow(clippy::result_large_err)]     // the state is the ULD working memory; no heap to box it in
+    pub fn go_to_sleep(/*move*/ self) -> core::result::Result<State_LP_Idle, (State_HP_Idle, Error)> {
+        State_LP_Idle::transition_from(self)
+    }
+
     /*
     * Change the I2C address on-the-fly and continue the session with the new I2C address.
     *
@@ -83,14 +134,15 @@ impl State_HP_Idle {
 
             pl.wr_bytes(0x7fff, &[2])?;  // now with the new I2C address
             Ok(())
-        }).expect("writing to I2C to succeed");
+        }).map_err(|_| Error::PlatformI2c)?;
 
         // Further comms will happen to the new address. Let's still make a small access with the
         // new address, e.g. reading something.
         //
-        self.i2c_no_op().map_err(|e| {
+        self.i2c_no_op().map_err(|_e| {
             #[cfg(feature = "defmt")]
-            error!("Device wasn't reached after its I2C address changed."); e
+            error!("Device wasn't reached after its I2C address changed: {}", _e);
+            Error::AddrChangeNotConfirmed
         })?;
 
         Ok(())
@@ -102,41 +154,27 @@ impl State_HP_Idle {
         let mut tmp: u8 = 0;
         match unsafe { vl53l5cx_get_power_mode(&mut self.uld, &mut tmp) } {
             ST_OK => Ok(()),
-            e => Err(Error(e))
+            e => Err(Error::from_uld(e))
         }
     }
 
-    /*R not needed
     pub(crate) fn borrow_uld(&self) -> &VL53L5CX_Configuration {
         &self.uld
-    }*/
+    }
 
     pub(crate) fn borrow_uld_mut(&mut self) -> &mut VL53L5CX_Configuration {
         &mut self.uld
     }
 
-    /*** disabled (until we try/need low power)
-    // tbd. Does setting low power mode mean transitioning to 'LP_Idle'?  In that case, this should
-    //      be state transition for us (and 'get_power_mode' is not needed, since it's implied by
-    //      the Rust object the application has access to!
-
-    //---
-    // Maintenance; special use
-    //
-    pub fn get_power_mode(&mut self) -> Result<PowerMode> {
-        let mut tmp: u8 = 0;
-        match unsafe { vl53l5cx_get_power_mode(&mut self.vl, &mut tmp) } {
-            ST_OK => Ok(PowerMode::from_repr(tmp).unwrap()),
-            e => Err(Error(e))
-        }
-    }
-    pub fn set_power_mode(&mut self, v: PowerMode) -> Result<()> {
-        match unsafe { vl53l5cx_set_power_mode(&mut self.vl, v as u8) } {
-            ST_OK => Ok(()),
-            e => Err(Error(e))
-        }
+    /*
+    * A consuming method, used when moving to "LP Idle" state.
+    */
+    pub(crate) fn into_uld(self) -> VL53L5CX_Configuration {
+        self.uld
     }
-    ***/
+
+    // Note: Power mode is not exposed as a getter/setter. The power mode is implied by the state
+    //      (Rust object) the application has access to: 'State_HP_Idle' or 'State_LP_Idle'.
 
     // tbd. if exposing these, make them into a "dci" feature
     //pub fn dci_read_data(index: u16, buf: &mut [u8]) { unimplemented!() }
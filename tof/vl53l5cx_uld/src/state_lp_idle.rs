@@ -0,0 +1,91 @@
+/*
+* State_LP_Idle
+*
+* The "LP Idle" state (vendor terminology): the sensor is kept in low power mode, but it retains
+* its firmware, configuration and I2C address. Waking up is much faster than a fresh '.init()',
+* and doesn't need the I2C address dance to be redone.
+*
+* The power mode can only be changed while the sensor is not ranging |*|, and the sensor needs to
+* be woken up before anything else is done with it. The Rust API reflects this by only allowing
+* the transitions 'HP Idle' <-> 'LP Idle'. While in 'LP Idle', you don't have access to any other
+* sensor functionality.
+*
+* If a transition fails (e.g. an I2C glitch), the state it started from is given back with the
+* error, so that the caller can try again. The sensor's power mode is then not known; trying
+* again is fine, either way.
+*
+*   [*]: DS13754 - Rev 12, p.9
+*/
+#[cfg(feature = "defmt")]
+use defmt::debug;
+
+use core::result::Result as CoreResult;
+
+use crate::{
+    state_hp_idle::State_HP_Idle,
+    uld_raw::{
+        vl53l5cx_set_power_mode,
+        PowerMode as PowerMode_R,
+        VL53L5CX_Configuration,
+    },
+    Error,
+    Result,
+    ST_OK
+};
+
+#[allow(non_camel_case_types)]
+pub struct State_LP_Idle {
+    // Same ULD "playing ground" as with 'State_HP_Idle'; just kept out of reach while the sensor
+    // sleeps.
+    uld: VL53L5CX_Configuration,
+}
+
+// Only the name; the ULD memory isn't for showing. Lets the '(state, Error)' results be '.unwrap()'ed.
+impl core::fmt::Debug for State_LP_Idle {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str("State_LP_Idle")
+    }
+}
+
+impl State_LP_Idle {
+    #[allow(clippy::result_large_err)]     // the state is the ULD working memory; no heap to box it in
+    pub(crate) fn transition_from(/*move*/ st: State_HP_Idle) -> CoreResult<Self, (State_HP_Idle, Error)> {
+        let mut uld = st.into_uld();
+
+        match set_power_mode(&mut uld, PowerMode_R::SLEEP) {
+            Ok(()) => {
+                #[cfg(feature = "defmt")]
+                debug!("Sensor is now in LP Idle");
+
+                Ok(Self{ uld })
+            },
+            Err(e) => Err((State_HP_Idle::new(uld), e))
+        }
+    }
+
+    /*
+    * Bring the sensor back to 'HP Idle'. Firmware, configuration and the I2C address remain as
+    * they were before '.go_to_sleep()'.
+    *
+    * On failure, the 'LP Idle' state comes back with the error; call '.wake_up()' on it again.
+    */
+    #[allow(clippy::result_large_err)]
+    pub fn wake_up(/*move*/ mut self) -> CoreResult<State_HP_Idle, (Self, Error)> {
+        match set_power_mode(&mut self.uld, PowerMode_R::WAKEUP) {
+            Ok(()) => {
+                #[cfg(feature = "defmt")]
+                debug!("Sensor woke up to HP Idle");
+
+                Ok( State_HP_Idle::new(self.uld) )
+            },
+            Err(e) => Err((self, e))
+        }
+    }
+}
+
+fn set_power_mode(uld: &mut VL53L5CX_Configuration, v: PowerMode_R) -> Result<()> {
+    match unsafe { vl53l5cx_set_power_mode(uld, v as u8) } {
+        ST_OK => Ok(()),
+        e => Err(Error::from_uld(e))
+    }
+}
@@ -5,13 +5,19 @@
 *   'State_Ranging':  handle to the sensor once ranging is ongoing
 */
 #[cfg(feature = "defmt")]
-use defmt::{assert, panic, trace};
+use defmt::{panic, trace};
 
 use crate::uld_raw::{
     VL53L5CX_Configuration,
     vl53l5cx_start_ranging,
     vl53l5cx_check_data_ready,
     vl53l5cx_get_ranging_data,
+    vl53l5cx_get_resolution,
+    vl53l5cx_get_ranging_frequency_hz,
+    vl53l5cx_get_ranging_mode,
+    vl53l5cx_get_integration_time_ms,
+    vl53l5cx_get_sharpener_percent,
+    vl53l5cx_get_target_order,
     vl53l5cx_set_resolution,
     vl53l5cx_set_ranging_frequency_hz,
     vl53l5cx_set_ranging_mode,
@@ -26,10 +32,18 @@ use crate::uld_raw::{
     VL53L5CX_ResultsData
 };
 
+#[cfg(feature = "motion_indicator")]
+use crate::motion_indicator::{MotionConfig, MotionMap};
+#[cfg(feature = "detection_thresholds")]
+use crate::detection_thresholds::{self, ThresholdsConfig};
+
 use crate::{
+    frame_stats::FrameStats,
+    orientation::Orientation,
     results_data::ResultsData,
     state_hp_idle::State_HP_Idle,
     units::{MsU16, HzU8, PrcU8, TempC, ExtU32 as _},
+    ConfigError,
     Error,
     Result,
 };
@@ -40,16 +54,48 @@ use {
 };
 
 /*
-* Defines which resolutions the device is able to play with; their mapping to ULD and physical
-* limits.
+* Resolution, for when it's only known at runtime. The const generic 'DIM' (4|8) remains the
+* primary (zero-cost) way; this enum is used by the '..._Dyn' types.
 */
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Resolution {
+    _4X4,
+    _8X8
+}
+
+impl Resolution {
+    // Only for the 'DIM' of our own types; anything but 4|8 is a bug on our side.
+    pub(crate) const fn from_dim(dim: usize) -> Self {
+        match dim {
+            4 => Self::_4X4,
+            8 => Self::_8X8,
+            _ => unreachable!()
+        }
+    }
+
+    pub const fn dim(&self) -> usize {
+        match self {
+            Self::_4X4 => 4,
+            Self::_8X8 => 8
+        }
+    }
+
+    /*
+    * Defines which resolutions the device is able to play with; their mapping to ULD and physical
+    * limits.
+    */
+    pub(crate) const fn details(&self) -> (Resolution_R /*Raw entry*/, u8 /*integration times*/, HzU8 /*max freq*/) {
+        match self {
+            Self::_4X4 => { (Resolution_R::_4X4, 1, HzU8(60)) },
+            Self::_8X8 => { (Resolution_R::_8X8, 4, HzU8(15)) },
+        }
+    }
+}
+
 // Rust note: 'const fn' gets evaluated at compile time.
 const fn reso_details<const DIM: usize>() -> (Resolution_R /*Raw entry*/, u8 /*integration time*/, HzU8 /*max freq*/) {
-    match DIM {
-        4 => { (Resolution_R::_4X4, 1, HzU8(15)) },
-        8 => { (Resolution_R::_8X8, 4, HzU8(60)) },
-        _ => unreachable!()
-    }
+    Resolution::from_dim(DIM).details()
 }
 
 // Adding to the C API by joining integration time with the ranging mode - since integration time
@@ -99,7 +145,7 @@ impl TargetOrder {
 *       = Integration happens within each ranging period. In fact, there should be a 1ms margin
 *       left.
 *   - "[...] select your [ranging] resolution before [setting the frequency]"
-*       - range is [1..60] (4x4) or [1..15 (8x8); ranges inclusive
+*       - range is [1..60] (4x4) or [1..15] (8x8); ranges inclusive
 *   - Integration time and frequency only apply to AUTONOMOUS ranging mode
 *   - Integration time range is (for all resolutions): [2ms..1000ms]; inclusive
 *   - Sharpener range is [0..99]; inclusive; (0 = disabled)
@@ -109,6 +155,11 @@ pub struct RangingConfig<const DIM: usize /*= 4*/> {    // |*|
     mode: Mode,      // also carries ranging frequency and integration time for 'AUTONOMOUS'
     sharpener: Option<PrcU8>,       // value range: 1..=99
     target_order: TargetOrder,
+    orientation: Orientation,       // only on our side; the sensor doesn't know
+    #[cfg(feature = "motion_indicator")]
+    motion: MotionConfig,           // applied after the resolution is set
+    #[cfg(feature = "detection_thresholds")]
+    thresholds: Option<ThresholdsConfig>,   // 'None': INT fires for each frame
 }
     // |*|: decided to /not/ provide a '= 4' default for the 'DIM'. It *would work*, and slightly
     //      make it easier for the _application layer_, but it also messes with compile errors,
@@ -120,10 +171,14 @@ pub struct RangingConfig<const DIM: usize /*= 4*/> {    // |*|
 
 impl<const DIM: usize> RangingConfig<DIM> {
     /* We allow construction to make potentially incompatible combinations, but check them within
-    * '.apply()'. This is a compromise between simplicity and robustness. Note that some obvious
-    * type-system robustness has been done, e.g. bundling ranging frequency and integration times
-    * with the ranging mode (since those only apply to one mode).
+    * '.try_validate()' (also called by '.apply()'). This is a compromise between simplicity and
+    * robustness. Note that some obvious type-system robustness has been done, e.g. bundling ranging
+    * frequency and integration times with the ranging mode (since those only apply to one mode).
     */
+    pub const fn resolution(&self) -> Resolution {
+        Resolution::from_dim(DIM)
+    }
+
     pub fn with_sharpener(/*move*/ self, v: PrcU8) -> Self {
         Self { sharpener: Some(v), ..self }
     }
@@ -136,45 +191,90 @@ impl<const DIM: usize> RangingConfig<DIM> {
         Self { mode, ..self }
     }
 
-    fn validate(&self) {
+    /*
+    * How the sensor is mounted; the results are turned accordingly.
+    */
+    pub fn with_orientation(/*move*/ self, orientation: Orientation) -> Self {
+        Self { orientation, ..self }
+    }
+
+    pub fn mode(&self) -> Mode { self.mode }
+    pub fn sharpener(&self) -> Option<PrcU8> { self.sharpener }
+    pub fn target_order(&self) -> TargetOrder { self.target_order }
+    pub fn orientation(&self) -> Orientation { self.orientation }
+
+    #[cfg(feature = "motion_indicator")]
+    pub fn with_motion(/*move*/ self, motion: MotionConfig) -> Self {
+        Self { motion, ..self }
+    }
+
+    /*
+    * Only raise INT when (any of) the thresholds trip.
+    */
+    #[cfg(feature = "detection_thresholds")]
+    pub fn with_thresholds(/*move*/ self, thresholds: ThresholdsConfig) -> Self {
+        Self { thresholds: Some(thresholds), ..self }
+    }
+
+    #[cfg(feature = "detection_thresholds")]
+    pub fn thresholds(&self) -> Option<&ThresholdsConfig> { self.thresholds.as_ref() }
+
+    /*
+    * Check the config against the vendor limits, without touching the sensor. '.start_ranging()'
+    * does this as well, but the application may want to check e.g. a config received over the air
+    * before committing to it.
+    */
+    pub fn try_validate(&self) -> core::result::Result<(), ConfigError> {
         let (_,R_INTEGRATION_TIMES_N, R_FREQ_RANGE_MAX): (_,u8,HzU8) = reso_details::<DIM>();
+        let dim = DIM as u8;
 
-        match self.mode {
-            AUTONOMOUS(MsU16(integration_time_ms), HzU8(freq)) => {
-                assert!((2..=1000).contains(&integration_time_ms), "Integration time out of range");
-
-                // "The sum of all integration times + 1 ms overhead must be lower than the measurement
-                // period. Otherwise, the ranging period is automatically increased." (src: UM2884 - Rev 5 p.9)
-                //
-                // "4x4 is composed of one integration time"
-                // "8x8 is composed of four integration times" (same src)
-                //
-                let n = R_INTEGRATION_TIMES_N;  // 1 (4x4); 4 (8x8)
-
-                // Note: The test itself is calculated so that inaccuracies don't occur (multiplication instead of division).
-                //      The error message parameter is let go more loosely; not a problem.
-                //
-                assert!((integration_time_ms as u16+1)*(n as u16)*(freq as u16) < 1000,
-                    "Integration time exceeds the available window ({}ms)", (1000_u16/(n as u16 * freq as u16))-1
-                );
-
-                let freq_range = 1..(R_FREQ_RANGE_MAX.0 as u8);    // 1..15 (4x4); 1..60 (8x8)
-                assert!(freq_range.contains(&freq), "Frequency out of range");
-            },
-            _ => {}
+        if let AUTONOMOUS(MsU16(ms), HzU8(hz)) = self.mode {
+            // Frequency first; the other checks divide by it.
+            let max_hz = R_FREQ_RANGE_MAX.0;    // 60 (4x4); 15 (8x8)
+            if !(1..=max_hz).contains(&hz) {
+                return Err(ConfigError::FrequencyOutOfRange{ dim, hz, max_hz });
+            }
+
+            if !(2..=1000).contains(&ms) {
+                return Err(ConfigError::IntegrationTimeOutOfRange{ ms });
+            }
+
+            // "The sum of all integration times + 1 ms overhead must be lower than the measurement
+            // period. Otherwise, the ranging period is automatically increased." (src: UM2884 - Rev 5 p.9)
+            //
+            // "4x4 is composed of one integration time"
+            // "8x8 is composed of four integration times" (same src)
+            //
+            // This also covers "Integration time must be [...] lower than the ranging period, for a
+            // selected resolution." (source: C ULD sources)
+            //
+            // Note: Calculated so that inaccuracies don't occur (multiplication instead of division).
+            //
+            let n = R_INTEGRATION_TIMES_N as u32;  // 1 (4x4); 4 (8x8)
+
+            if (ms as u32 +1)*n*(hz as u32) >= 1000 {
+                // largest 'ms' that passes the above
+                let max_ms = (999/(n*hz as u32)) as u16 - 1;
+                return Err(ConfigError::IntegrationExceedsWindow{ dim, hz, max_ms });
+            }
         }
 
         match self.sharpener {
-            Some(PrcU8(v)) => { assert!((1..=99).contains(&v), "Sharpener out of range (1..=99)") },
-            None => {}
+            Some(PrcU8(prc)) if !(1..=99).contains(&prc) => { return Err(ConfigError::SharpenerOutOfRange{ prc }) },
+            _ => {}
         }
 
-        // "Integration time must be [...] lower than the ranging period, for a selected resolution." (source: C ULD sources)
-        //  tbd. Uncypher what that means, check it as well.
+        #[cfg(feature = "motion_indicator")]
+        self.motion.try_validate()?;
+
+        #[cfg(feature = "detection_thresholds")]
+        if let Some(ref x) = self.thresholds { x.try_validate::<DIM>()?; }
+
+        Ok(())
     }
 
     fn apply(&self, vl: &mut VL53L5CX_Configuration) -> Result<()> {
-        self.validate();    // may panic
+        self.try_validate()?;   // nothing sent to the sensor, if the config is bad
         let ULD_RESO: Resolution_R = reso_details::<DIM>().0;
 
         // Set the resolution first. UM2884 (Rev 5) says:
@@ -182,23 +282,23 @@ impl<const DIM: usize> RangingConfig<DIM> {
 
         match unsafe { vl53l5cx_set_resolution(vl, ULD_RESO as u8) } {  // reso value: 16 (4x4); 64 (8x8)
             ST_OK => Ok(()),
-            e => Err(Error(e))
+            e => Err(Error::from_uld(e))
         }?;
 
         if let AUTONOMOUS(MsU16(ms), HzU8(freq)) = self.mode {
             match unsafe { vl53l5cx_set_integration_time_ms(vl, ms as u32) } {
                 ST_OK => Ok(()),
-                e => Err(Error(e))
+                e => Err(Error::from_uld(e))
             }?;
-            match unsafe { vl53l5cx_set_ranging_frequency_hz(vl, freq as u8) } {
+            match unsafe { vl53l5cx_set_ranging_frequency_hz(vl, freq) } {
                 ST_OK => Ok(()),
-                e => Err(Error(e))
+                e => Err(Error::from_uld(e))
             }?;
         }
 
         match unsafe { vl53l5cx_set_ranging_mode(vl, self.mode.as_uld() as _) } {
             ST_OK => Ok(()),
-            e => Err(Error(e))
+            e => Err(Error::from_uld(e))
         }?;
 
         let sharpener_prc: u8 = match self.sharpener {
@@ -207,16 +307,180 @@ impl<const DIM: usize> RangingConfig<DIM> {
         };
         match unsafe { vl53l5cx_set_sharpener_percent(vl, sharpener_prc) } {
             ST_OK => Ok(()),
-            e => Err(Error(e))
+            e => Err(Error::from_uld(e))
         }?;
 
         match unsafe { vl53l5cx_set_target_order(vl, self.target_order.as_uld() as _) } {
             ST_OK => Ok(()),
-            e => Err(Error(e))
+            e => Err(Error::from_uld(e))
         }?;
 
         Ok(())
     }
+
+    /*
+    * Read back what the sensor has been set to: resolution, mode, frequency and integration time,
+    * sharpener and target order. A resolution other than 'DIM', or values we don't know, give
+    * 'Error::ConfigMismatch'.
+    *
+    * Orientation (only on our side), motion indicator and thresholds are not read; they are left
+    * at their defaults.
+    */
+    pub(crate) fn read_from(vl: &mut VL53L5CX_Configuration) -> Result<Self> {
+        let mut reso: u8 = 0;
+        match unsafe { vl53l5cx_get_resolution(vl, &mut reso) } {
+            ST_OK => Ok(()),
+            e => Err(Error::from_uld(e))
+        }?;
+        if reso != reso_details::<DIM>().0 as u8 {
+            return Err(Error::ConfigMismatch);
+        }
+
+        let mut mode_raw: u8 = 0;
+        match unsafe { vl53l5cx_get_ranging_mode(vl, &mut mode_raw) } {
+            ST_OK => Ok(()),
+            e => Err(Error::from_uld(e))
+        }?;
+
+        let mode = match RangingMode_R::from_repr(mode_raw) {
+            Some(RangingMode_R::CONTINUOUS) => CONTINUOUS,
+            Some(RangingMode_R::AUTONOMOUS) => {
+                let mut ms: u32 = 0;
+                match unsafe { vl53l5cx_get_integration_time_ms(vl, &mut ms) } {
+                    ST_OK => Ok(()),
+                    e => Err(Error::from_uld(e))
+                }?;
+                let mut hz: u8 = 0;
+                match unsafe { vl53l5cx_get_ranging_frequency_hz(vl, &mut hz) } {
+                    ST_OK => Ok(()),
+                    e => Err(Error::from_uld(e))
+                }?;
+
+                let ms = u16::try_from(ms).map_err(|_| Error::ConfigMismatch)?;
+                AUTONOMOUS(MsU16(ms), HzU8(hz))
+            },
+            None => return Err(Error::ConfigMismatch)
+        };
+
+        let mut prc: u8 = 0;
+        match unsafe { vl53l5cx_get_sharpener_percent(vl, &mut prc) } {
+            ST_OK => Ok(()),
+            e => Err(Error::from_uld(e))
+        }?;
+        let sharpener = match prc { 0 => None, v => Some(PrcU8(v)) };
+
+        let mut order_raw: u8 = 0;
+        match unsafe { vl53l5cx_get_target_order(vl, &mut order_raw) } {
+            ST_OK => Ok(()),
+            e => Err(Error::from_uld(e))
+        }?;
+        let target_order = match TargetOrder_R::from_repr(order_raw) {
+            Some(TargetOrder_R::CLOSEST) => CLOSEST,
+            Some(TargetOrder_R::STRONGEST) => STRONGEST,
+            None => return Err(Error::ConfigMismatch)
+        };
+
+        Ok(Self{ mode, sharpener, target_order, ..Self::default() })
+    }
+
+    /*
+    * Would the sensor, set to 'self', read back as 'got'? Only compares what '.read_from()' reads.
+    */
+    pub(crate) fn same_on_sensor(&self, got: &Self) -> bool {
+        let mode_same = match (self.mode, got.mode) {
+            (CONTINUOUS, CONTINUOUS) => true,
+            (AUTONOMOUS(MsU16(a), HzU8(b)), AUTONOMOUS(MsU16(x), HzU8(y))) => (a, b) == (x, y),
+            _ => false
+        };
+        let order_same = matches!((self.target_order, got.target_order), (CLOSEST, CLOSEST) | (STRONGEST, STRONGEST));
+
+        mode_same && order_same && sharpener_read_back(self.sharpener) == got.sharpener.map(|PrcU8(v)| v)
+    }
+}
+
+/*
+* The sensor keeps the sharpener as '0..=255'; what's set ('prc*255/100') reads back as
+* '(that*100/255)', rounded down. E.g. 33% reads back as 32%, and 1% as 0 (disabled).
+*/
+fn sharpener_read_back(v: Option<PrcU8>) -> Option<u8> {
+    let prc = match v { Some(PrcU8(prc)) => prc as u32, None => 0 };
+    match (prc*255/100)*100/255 {
+        0 => None,
+        x => Some(x as u8)
+    }
+}
+
+/*
+* Fixed size, byte form of a 'RangingConfig'; for storing it (see 'persist.rs').
+*
+*   [0]         mode: 0 = CONTINUOUS, 1 = AUTONOMOUS
+*   [1..3]      integration time (ms; u16 LE)   (AUTONOMOUS; otherwise 0)
+*   [3]         ranging frequency (Hz)          -''-
+*   [4]         sharpener (%); 0 = none
+*   [5]         target order: 0 = CLOSEST, 1 = STRONGEST
+*   [6]         orientation (0..=7; as 'Orientation::ALL')
+*   [7]         0 (reserved)
+*   [8..10]     motion window min (mm; u16 LE); 0 if there's no 'motion_indicator'
+*   [10..12]    motion window max (mm; u16 LE)
+*
+* Note: Detection thresholds are not carried; 'ConfigRecord::store()' refuses configs that have them.
+*/
+#[cfg(feature = "persist")]
+pub(crate) const CFG_RECORD_SIZE: usize = 12;
+
+#[cfg(feature = "persist")]
+impl<const DIM: usize> RangingConfig<DIM> {
+    pub(crate) fn to_record(&self) -> [u8; CFG_RECORD_SIZE] {
+        let mut bs = [0_u8; CFG_RECORD_SIZE];
+
+        if let AUTONOMOUS(MsU16(ms), HzU8(hz)) = self.mode {
+            bs[0] = 1;
+            bs[1..3].copy_from_slice(&ms.to_le_bytes());
+            bs[3] = hz;
+        }
+        bs[4] = match self.sharpener { Some(PrcU8(v)) => v, None => 0 };
+        bs[5] = match self.target_order { CLOSEST => 0, STRONGEST => 1 };
+        bs[6] = self.orientation as u8;
+
+        #[cfg(feature = "motion_indicator")]
+        {
+            let (a, b) = self.motion.window();
+            bs[8..10].copy_from_slice(&a.to_le_bytes());
+            bs[10..12].copy_from_slice(&b.to_le_bytes());
+        }
+        bs
+    }
+
+    /*
+    * 'None' if a field has a value we don't know. The limits are not checked; that's for
+    * '.try_validate()'.
+    */
+    pub(crate) fn from_record(bs: &[u8; CFG_RECORD_SIZE]) -> Option<Self> {
+        let u16_at = |i: usize| u16::from_le_bytes([bs[i], bs[i+1]]);
+
+        let mode = match bs[0] {
+            0 => CONTINUOUS,
+            1 => AUTONOMOUS(MsU16(u16_at(1)), HzU8(bs[3])),
+            _ => return None
+        };
+        let sharpener = match bs[4] { 0 => None, v => Some(PrcU8(v)) };
+        let target_order = match bs[5] { 0 => CLOSEST, 1 => STRONGEST, _ => return None };
+        let orientation = *Orientation::ALL.get(bs[6] as usize)?;
+
+        #[cfg(feature = "motion_indicator")]
+        let motion = match (u16_at(8), u16_at(10)) {
+            (0, 0) => MotionConfig::default(),  // stored without the feature
+            (a, b) => MotionConfig::new(a, b),
+        };
+
+        Some(Self{
+            mode, sharpener, target_order, orientation,
+            #[cfg(feature = "motion_indicator")]
+            motion,
+            #[cfg(feature = "detection_thresholds")]
+            thresholds: None,
+        })
+    }
 }
 
 impl<const DIM: usize> Default for RangingConfig<DIM> {
@@ -228,6 +492,11 @@ impl<const DIM: usize> Default for RangingConfig<DIM> {
             sharpener: None,
             target_order: STRONGEST,
             mode: AUTONOMOUS(5.ms(),HzU8(1)/*1.Hz()*/),
+            orientation: Orientation::Upright,
+            #[cfg(feature = "motion_indicator")]
+            motion: MotionConfig::default(),
+            #[cfg(feature = "detection_thresholds")]
+            thresholds: None,
         }
     }
 }
@@ -238,6 +507,11 @@ pub struct State_Ranging<const DIM: usize> {    // DIM: 4|8
     // The 'Option' is needed to have both explicit '.stop()' and an implicit 'Drop'.
     outer_state: Option<State_HP_Idle>,
     //R rbuf: ResultsData<DIM>      // Rust-side results store
+    orientation: Orientation,
+    #[cfg(feature = "motion_indicator")]
+    motion_map: MotionMap,
+    frame_stats: FrameStats,
+    seen: Option<u8>,       // streamcount of the frame '.is_ready()' last saw; until read
 }
 
 impl<const DIM: usize> State_Ranging<DIM> {
@@ -245,15 +519,31 @@ impl<const DIM: usize> State_Ranging<DIM> {
         let vl: &mut VL53L5CX_Configuration = st.borrow_uld_mut();
         cfg.apply(vl)?;
 
+        // Motion indicator needs to know the resolution; thus after '.apply()'.
+        #[cfg(feature = "motion_indicator")]
+        let motion_map = cfg.motion.apply(vl, reso_details::<DIM>().0)?;
+
+        // The sensor keeps the thresholds (and their enabled state) over rangings; always set.
+        #[cfg(feature = "detection_thresholds")]
+        match cfg.thresholds {
+            Some(ref x) => x.apply::<DIM>(vl, cfg.orientation),
+            None => detection_thresholds::enable(vl, false)
+        }?;
+
         match unsafe { vl53l5cx_start_ranging(vl) } {
             ST_OK => {
                 let x = Self{
                     outer_state: Some(st),
                     //R rbuf: ResultsData::empty()
+                    orientation: cfg.orientation,
+                    #[cfg(feature = "motion_indicator")]
+                    motion_map,
+                    frame_stats: FrameStats::new(),
+                    seen: None,
                 };
                 Ok(x)
             },
-            e => Err(Error(e))
+            e => Err(Error::from_uld(e))
         }
     }
 
@@ -264,16 +554,51 @@ impl<const DIM: usize> State_Ranging<DIM> {
     pub fn is_ready(&mut self) -> Result<bool> {
         let mut tmp: u8 = 0;
         match unsafe { vl53l5cx_check_data_ready(self.borrow_uld_mut(), &mut tmp) } {
-            ST_OK => Ok(tmp != 0),
-            e => Err(Error(e))
+            ST_OK => {
+                if tmp != 0 {
+                    self.seen = Some(self.streamcount());
+                }
+                Ok(tmp != 0)
+            },
+            e => Err(Error::from_uld(e))
         }
     }
 
+    /*
+    * The sensor's counter (0..=254; wraps) of the frame last read. Consecutive frames differ by 1.
+    */
+    pub fn streamcount(&self) -> u8 {
+        self.outer_state.as_ref().unwrap().borrow_uld().streamcount
+    }
+
+    /*
+    * Counts of frames read, skipped, read twice, or read newer than seen; since the ranging
+    * started. See 'frame_stats.rs'.
+    */
+    pub fn frame_stats(&self) -> &FrameStats {
+        &self.frame_stats
+    }
+
     /*
     * Collect results from the last successful scan.
+    *
+    * Returns the results by value; at 8x8 (with many fields enabled) that is kilobytes. For a
+    * steady stream of frames, prefer '.get_data_into()'.
     //tbd. Try and describe what happens, if you call here before a scan is ready.  tbd. Make a test/example
     */
     pub fn get_data(&mut self) -> Result<(ResultsData<DIM>, TempC)> {
+        let mut res = ResultsData::empty();
+        let temp = self.get_data_into(&mut res)?;
+        Ok((res, temp))
+    }
+
+    /*
+    * Like '.get_data()', but writes the results into a buffer that the caller keeps (and reuses).
+    *
+    * Note: The ULD C API still needs its own 'VL53L5CX_ResultsData' (on the stack); it's converted
+    *       straight into 'out'.
+    */
+    pub fn get_data_into(&mut self, out: &mut ResultsData<DIM>) -> Result<TempC> {
         use core::mem::MaybeUninit;
         use core::ptr::addr_of_mut;
 
@@ -292,10 +617,18 @@ impl<const DIM: usize> State_Ranging<DIM> {
 
         match unsafe { vl53l5cx_get_ranging_data(self.borrow_uld_mut(), &mut buf) } {
             ST_OK => {
-                let tuple = ResultsData::<DIM>::from(&buf);
-                Ok(tuple)
+                // The ULD has updated 'streamcount' to that of the frame read.
+                let seen = self.seen.take();
+                self.frame_stats.record(self.streamcount(), seen);
+
+                let temp = out.feed(&buf, self.orientation);
+
+                #[cfg(feature = "motion_indicator")]
+                out.feed_motion(&buf, &self.motion_map, self.orientation);
+
+                Ok(temp)
             },
-            e => Err(Error(e))
+            e => Err(Error::from_uld(e))
         }
     }
 
@@ -311,6 +644,14 @@ impl<const DIM: usize> State_Ranging<DIM> {
         }
     }
 
+    /*
+    * Give up the session without telling the sensor; e.g. when it's wedged and about to be power
+    * cycled. (Dropping the state would try to stop the ranging, and panic if that fails.)
+    */
+    pub fn abandon(mut self) {
+        self.outer_state = None;
+    }
+
     /*
     * Lower level "stop", usable by both the explicit '.stop()' and 'Drop' handler.
     *
@@ -319,7 +660,7 @@ impl<const DIM: usize> State_Ranging<DIM> {
     fn _stop(outer: &mut State_HP_Idle) -> Result<()> {
         match unsafe { vl53l5cx_stop_ranging(outer.borrow_uld_mut()) } {
             ST_OK => Ok(()),
-            e => Err(Error(e))
+            e => Err(Error::from_uld(e))
         }
     }
 
@@ -342,7 +683,7 @@ impl<const DIM: usize> Drop for State_Ranging<DIM> {
             Some(ref mut outer) => {
                 match Self::_stop(outer) {
                     Ok(_) => {},
-                    Err(Error(e)) => { panic!("Stop ranging failed; st={}", e) }
+                    Err(e) => { panic!("Stop ranging failed: {}", e) }
                 }
             }
         }
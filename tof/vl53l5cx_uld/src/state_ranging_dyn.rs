@@ -0,0 +1,125 @@
+/*
+* state_ranging_dyn.rs:
+*
+*   Runtime selectable resolution, on top of the const generic 'RangingConfig<DIM>' and
+*   'State_Ranging<DIM>'.
+*
+* The const generic types remain the primary API; they carry the resolution in the type and
+* cost nothing extra. When the resolution is decided at runtime (e.g. switching between fast 4x4
+* and detailed 8x8, based on what the application is doing), use these instead. They are plain
+* enums over the two resolutions, and dispatch to the generic code.
+*/
+use crate::{
+    frame_stats::FrameStats,
+    results_data::ResultsDataDyn,
+    state_hp_idle::State_HP_Idle,
+    state_ranging::{
+        RangingConfig,
+        Resolution,
+        State_Ranging,
+    },
+    units::TempC,
+    ConfigError,
+    Result,
+};
+
+#[derive(Clone)]
+pub enum RangingConfigDyn {
+    _4X4(RangingConfig<4>),
+    _8X8(RangingConfig<8>),
+}
+
+impl RangingConfigDyn {
+    pub fn resolution(&self) -> Resolution {
+        match self {
+            Self::_4X4(_) => Resolution::_4X4,
+            Self::_8X8(_) => Resolution::_8X8,
+        }
+    }
+
+    pub fn try_validate(&self) -> core::result::Result<(), ConfigError> {
+        match self {
+            Self::_4X4(c) => c.try_validate(),
+            Self::_8X8(c) => c.try_validate(),
+        }
+    }
+}
+
+impl From<RangingConfig<4>> for RangingConfigDyn {
+    fn from(c: RangingConfig<4>) -> Self { Self::_4X4(c) }
+}
+
+impl From<RangingConfig<8>> for RangingConfigDyn {
+    fn from(c: RangingConfig<8>) -> Self { Self::_8X8(c) }
+}
+
+#[allow(non_camel_case_types)]
+pub enum State_Ranging_Dyn {
+    _4X4(State_Ranging<4>),
+    _8X8(State_Ranging<8>),
+}
+
+impl State_Ranging_Dyn {
+    pub(crate) fn transition_from(/*move*/ st: State_HP_Idle, cfg: &RangingConfigDyn) -> Result<Self> {
+        match cfg {
+            RangingConfigDyn::_4X4(c) => Ok(Self::_4X4( State_Ranging::transition_from(st, c)? )),
+            RangingConfigDyn::_8X8(c) => Ok(Self::_8X8( State_Ranging::transition_from(st, c)? )),
+        }
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        match self {
+            Self::_4X4(_) => Resolution::_4X4,
+            Self::_8X8(_) => Resolution::_8X8,
+        }
+    }
+
+    pub fn is_ready(&mut self) -> Result<bool> {
+        match self {
+            Self::_4X4(r) => r.is_ready(),
+            Self::_8X8(r) => r.is_ready(),
+        }
+    }
+
+    pub fn streamcount(&self) -> u8 {
+        match self {
+            Self::_4X4(r) => r.streamcount(),
+            Self::_8X8(r) => r.streamcount(),
+        }
+    }
+
+    pub fn frame_stats(&self) -> &FrameStats {
+        match self {
+            Self::_4X4(r) => r.frame_stats(),
+            Self::_8X8(r) => r.frame_stats(),
+        }
+    }
+
+    pub fn get_data(&mut self) -> Result<(ResultsDataDyn, TempC)> {
+        match self {
+            Self::_4X4(r) => r.get_data().map(|(res,temp)| (ResultsDataDyn::_4X4(res), temp)),
+            Self::_8X8(r) => r.get_data().map(|(res,temp)| (ResultsDataDyn::_8X8(res), temp)),
+        }
+    }
+
+    /*
+    * If 'out' is of the other resolution, it's replaced by an empty one (of the right resolution).
+    */
+    pub fn get_data_into(&mut self, out: &mut ResultsDataDyn) -> Result<TempC> {
+        if out.resolution() != self.resolution() {
+            *out = ResultsDataDyn::empty(self.resolution());
+        }
+        match (self, out) {
+            (Self::_4X4(r), ResultsDataDyn::_4X4(o)) => r.get_data_into(o),
+            (Self::_8X8(r), ResultsDataDyn::_8X8(o)) => r.get_data_into(o),
+            _ => unreachable!()
+        }
+    }
+
+    pub fn stop(self) -> Result<State_HP_Idle> {
+        match self {
+            Self::_4X4(r) => r.stop(),
+            Self::_8X8(r) => r.stop(),
+        }
+    }
+}
@@ -0,0 +1,281 @@
+/*
+* Recording and replaying the 'Platform' traffic.
+*
+* Capture a session with a real sensor (on the bench) once, by wrapping the app's 'Platform' in a
+* 'RecordingPlatform'. Later, run the same driver steps against a 'ReplayPlatform' (e.g. on the
+* host), to see that a changed driver (or ULD version) still talks to the sensor the same way.
+*
+* Trace format (version 1); all integers little endian:
+*   <<
+*       "VLTR" 0x01                         header: magic, version
+*
+*       records, each starting with a tag byte:
+*           0x01  RD     index:u16 n:u16 data[n]        read; 'data' as the sensor gave it
+*           0x02  WR     index:u16 n:u16 data[n]        write; 'n' <= 64
+*           0x03  WR_H   index:u16 n:u16 fnv1a:u32      write; 'n' > 64 (e.g. firmware upload)
+*           0x04  DELAY  ms:u32
+*           0x05  ADDR   addr:u8                        'addr_changed()'; 7-bit
+*
+*       Failed I2C calls have the tag | 0x80; 0x81 (RD) has no 'data'.
+*   <<
+*
+* Long writes carry only a hash (32-bit FNV-1a of the data), to keep the traces small. Reads are
+* always in full; replay needs them.
+*
+* 'tools/vltrace' decodes a trace to text.
+*/
+#![cfg(feature = "trace")]
+
+use core::result::Result as CoreResult;
+
+use crate::{
+    I2cAddr,
+    Platform,
+};
+
+pub const TRACE_MAGIC: &[u8; 4] = b"VLTR";
+pub const TRACE_VERSION: u8 = 1;
+
+const RD: u8 = 0x01;
+const WR: u8 = 0x02;
+const WR_H: u8 = 0x03;
+const DELAY: u8 = 0x04;
+const ADDR: u8 = 0x05;
+const FAILED: u8 = 0x80;
+
+const WR_FULL_MAX: usize = 64;      // longer writes are hashed
+
+/*
+* Where the trace bytes go. A closure does; e.g. to a static buffer, or out over RTT.
+*/
+pub trait TraceSink {
+    fn put(&mut self, bs: &[u8]);
+}
+
+impl<F: FnMut(&[u8])> TraceSink for F {
+    fn put(&mut self, bs: &[u8]) { self(bs) }
+}
+
+pub struct RecordingPlatform<P: Platform, S: TraceSink> {
+    p: P,
+    sink: S,
+}
+
+impl<P: Platform, S: TraceSink> RecordingPlatform<P,S> {
+    /*
+    * Writes the header to 'sink' right away.
+    */
+    pub fn new(p: P, mut sink: S) -> Self {
+        sink.put(TRACE_MAGIC);
+        sink.put(&[TRACE_VERSION]);
+        Self{ p, sink }
+    }
+
+    fn put_op(&mut self, tag: u8, ok: bool, index: u16, n: usize) {
+        let tag = if ok { tag } else { tag | FAILED };
+        let mut b = [tag, 0,0, 0,0];
+        b[1..3].copy_from_slice(&index.to_le_bytes());
+        b[3..5].copy_from_slice(&(n as u16).to_le_bytes());
+        self.sink.put(&b);
+    }
+}
+
+impl<P: Platform, S: TraceSink> Platform for RecordingPlatform<P,S> {
+    fn rd_bytes(&mut self, index: u16, buf: &mut [u8]) -> CoreResult<(),()> {
+        let ret = self.p.rd_bytes(index, buf);
+
+        self.put_op(RD, ret.is_ok(), index, buf.len());
+        if ret.is_ok() { self.sink.put(buf) };
+        ret
+    }
+
+    fn wr_bytes(&mut self, index: u16, vs: &[u8]) -> CoreResult<(),()> {
+        let ret = self.p.wr_bytes(index, vs);
+
+        if vs.len() <= WR_FULL_MAX {
+            self.put_op(WR, ret.is_ok(), index, vs.len());
+            self.sink.put(vs);
+        } else {
+            self.put_op(WR_H, ret.is_ok(), index, vs.len());
+            self.sink.put(&fnv1a(vs).to_le_bytes());
+        }
+        ret
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        self.p.delay_ms(ms);
+
+        self.sink.put(&[DELAY]);
+        self.sink.put(&ms.to_le_bytes());
+    }
+
+    fn addr_changed(&mut self, addr: &I2cAddr) {
+        self.p.addr_changed(addr);
+
+        self.sink.put(&[ADDR, addr.as_7bit()]);
+    }
+}
+
+/*
+* One 'Platform' call, as in the trace (or as the driver now made it).
+*/
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TraceOp {
+    Rd{ index: u16, n: u16 },
+    Wr{ index: u16, n: u16 },
+    Delay{ ms: u32 },
+    Addr{ addr: u8 },
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Divergence {
+    BadTrace{ at: usize },                                  // not a (version 1) trace, or truncated
+    OpMismatch{ at: usize, expected: TraceOp, got: TraceOp },
+    DataMismatch{ at: usize, op: TraceOp },                 // write data (or its hash) differs
+    TraceEnded{ got: TraceOp },                             // driver made more calls
+    NotConsumed{ at: usize },                               // driver made less calls (seen at drop)
+}
+
+/*
+* Plays back a trace. Reads get their data (and failures) from the trace; writes, delays and
+* address changes are compared against it.
+*
+* The first divergence is given to 'on_divergence'; after that, all calls fail. Dropping the
+* platform (i.e. the driver state) before the end of the trace counts as a divergence.
+*/
+pub struct ReplayPlatform<F: FnMut(Divergence)> {
+    trace: &'static [u8],
+    at: usize,      // next record
+    derailed: bool,
+    on_divergence: F,
+}
+
+impl<F: FnMut(Divergence)> ReplayPlatform<F> {
+    pub fn new(trace: &'static [u8], on_divergence: F) -> Self {
+        let mut x = Self{ trace, at: 5, derailed: false, on_divergence };
+
+        if trace.len() < 5 || &trace[..4] != TRACE_MAGIC || trace[4] != TRACE_VERSION {
+            x.diverge(Divergence::BadTrace{ at: 0 });
+        }
+        x
+    }
+
+    fn diverge(&mut self, d: Divergence) {
+        if !self.derailed {
+            self.derailed = true;
+            (self.on_divergence)(d);
+        }
+    }
+
+    /*
+    * Take the next record, if it's 'got': '(ok, payload)'.
+    */
+    fn next(&mut self, got: TraceOp) -> Option<(bool, &'static [u8])> {
+        if self.derailed { return None }
+
+        let at = self.at;
+        if at >= self.trace.len() {
+            self.diverge(Divergence::TraceEnded{ got });
+            return None;
+        }
+
+        match parse(self.trace, at) {
+            Some((op, ..)) if op != got => {
+                self.diverge(Divergence::OpMismatch{ at, expected: op, got });
+                None
+            },
+            Some((_, ok, payload, len)) => {
+                self.at = at + len;
+                Some((ok, payload))
+            },
+            None => {
+                self.diverge(Divergence::BadTrace{ at });
+                None
+            }
+        }
+    }
+}
+
+/*
+* Parse a record at 'at': '(op, ok, payload, record length)'. 'None' if the trace is broken.
+*
+* 'payload' is the read/write data, or the hash ('WR_H').
+*/
+fn parse(t: &[u8], at: usize) -> Option<(TraceOp, bool, &[u8], usize)> {
+    let u16_at = |i: usize| t.get(i..i+2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+
+    let (tag, ok) = (t[at] & !FAILED, t[at] & FAILED == 0);
+
+    let (op, n_payload) = match tag {
+        RD | WR | WR_H => {
+            let (index, n) = (u16_at(at+1)?, u16_at(at+3)?);
+            match tag {
+                RD => (TraceOp::Rd{ index, n }, if ok { n as usize } else { 0 }),
+                WR => (TraceOp::Wr{ index, n }, n as usize),
+                _ => (TraceOp::Wr{ index, n }, 4)
+            }
+        },
+        DELAY => {
+            let b = t.get(at+1..at+5)?;
+            return Some((TraceOp::Delay{ ms: u32::from_le_bytes([b[0],b[1],b[2],b[3]]) }, true, &[], 5));
+        },
+        ADDR => {
+            return Some((TraceOp::Addr{ addr: *t.get(at+1)? }, true, &[], 2));
+        },
+        _ => return None
+    };
+
+    let payload = t.get(at+5 .. at+5+n_payload)?;
+    Some((op, ok, payload, 5+n_payload))
+}
+
+impl<F: FnMut(Divergence)> Platform for ReplayPlatform<F> {
+    fn rd_bytes(&mut self, index: u16, buf: &mut [u8]) -> CoreResult<(),()> {
+        match self.next(TraceOp::Rd{ index, n: buf.len() as u16 }) {
+            Some((true, data)) => { buf.copy_from_slice(data); Ok(()) },
+            _ => Err(())
+        }
+    }
+
+    fn wr_bytes(&mut self, index: u16, vs: &[u8]) -> CoreResult<(),()> {
+        let at = self.at;
+        let op = TraceOp::Wr{ index, n: vs.len() as u16 };
+
+        match self.next(op) {
+            Some((ok, expected)) => {
+                let same = if vs.len() <= WR_FULL_MAX { expected == vs } else { expected == fnv1a(vs).to_le_bytes() };
+                if !same {
+                    self.diverge(Divergence::DataMismatch{ at, op });
+                    return Err(());
+                }
+                if ok { Ok(()) } else { Err(()) }
+            },
+            None => Err(())
+        }
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        self.next(TraceOp::Delay{ ms });
+    }
+
+    fn addr_changed(&mut self, addr: &I2cAddr) {
+        self.next(TraceOp::Addr{ addr: addr.as_7bit() });
+    }
+}
+
+impl<F: FnMut(Divergence)> Drop for ReplayPlatform<F> {
+    fn drop(&mut self) {
+        if self.at < self.trace.len() {
+            self.diverge(Divergence::NotConsumed{ at: self.at });
+        }
+    }
+}
+
+/*
+* 32-bit FNV-1a
+*/
+pub fn fnv1a(bs: &[u8]) -> u32 {
+    bs.iter().fold(0x811c_9dc5_u32, |h, b| (h ^ *b as u32).wrapping_mul(0x0100_0193))
+}
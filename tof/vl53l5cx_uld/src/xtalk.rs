@@ -0,0 +1,136 @@
+/*
+* Crosstalk (xtalk) calibration (vendor plugin)
+*
+* When the sensor is mounted behind a cover glass, some of the emitted light reflects back from
+* the glass itself. Without calibration, this shows as phantom targets / shortened distances.
+*
+* The vendor's approach:
+*   1. Calibrate once, in a controlled setup: a reflective target of known reflectance, at a
+*       known distance, and nothing else in the field of view.
+*   2. Store the resulting 776 bytes (e.g. in flash).
+*   3. After each '.init()', give the stored data back to the sensor.
+*
+* Without (2..3), the ULD uses a default table (no cover glass).
+*
+* References:
+*   - vendor's UM2884 > "Crosstalk (xtalk) calibration"; Rev 5
+*/
+#![cfg(feature = "xtalk")]
+
+#[cfg(feature = "defmt")]
+use defmt::debug;
+
+use core::array::TryFromSliceError;
+
+use crate::uld_raw::{
+    vl53l5cx_calibrate_xtalk,
+    vl53l5cx_get_caldata_xtalk,
+    vl53l5cx_set_caldata_xtalk,
+    XTALK_BUFFER_SIZE,
+    ST_OK,
+};
+use crate::{
+    state_hp_idle::State_HP_Idle,
+    units::PrcU8,
+    ConfigError,
+    Error,
+    Result,
+};
+
+pub const XTALK_SIZE: usize = XTALK_BUFFER_SIZE as usize;     // 776
+
+/*
+* Crosstalk calibration data, as an opaque blob.
+*
+* The contents are only meaningful to the sensor. The blob is specific to the sensor + cover glass
+* combination it was taken with.
+*/
+#[derive(Clone)]
+pub struct XtalkData([u8; XTALK_SIZE]);
+
+impl XtalkData {
+    pub fn as_bytes(&self) -> &[u8; XTALK_SIZE] {
+        &self.0
+    }
+
+    pub fn from_bytes(bs: &[u8; XTALK_SIZE]) -> Self {
+        Self(*bs)
+    }
+}
+
+/*
+* For reading back from storage, where the length is not known by the type system.
+*/
+impl TryFrom<&[u8]> for XtalkData {
+    type Error = TryFromSliceError;
+
+    fn try_from(bs: &[u8]) -> core::result::Result<Self, Self::Error> {
+        Ok( Self(bs.try_into()?) )
+    }
+}
+
+impl State_HP_Idle {
+    /*
+    * Run the crosstalk calibration. The sensor needs to face a target of 'reflectance' at
+    * 'distance_mm' (and nothing else).
+    *
+    *   - reflectance: [1..99]%; inclusive
+    *   - samples: [1..16]; inclusive. More samples means better accuracy, but takes longer.
+    *   - distance_mm: [600..3000]; inclusive
+    *
+    * The vendor example uses (3%, 4 samples, 600mm).
+    *
+    * Arguments out of the ranges give 'Error::Config'; nothing is sent to the sensor.
+    *
+    * The calibration takes a while (seconds). Once done, the results are in use, and can be read
+    * by '.xtalk_data()' for storing.
+    */
+    pub fn calibrate_xtalk(&mut self, reflectance: PrcU8, samples: u8, distance_mm: u16) -> Result<()> {
+        let (PrcU8(refl), mm) = (reflectance, distance_mm);
+
+        if !(1..=99).contains(&refl) {
+            return Err(ConfigError::XtalkReflectanceOutOfRange{ prc: refl }.into());
+        }
+        if !(1..=16).contains(&samples) {
+            return Err(ConfigError::XtalkSamplesOutOfRange{ n: samples }.into());
+        }
+        if !(600..=3000).contains(&mm) {
+            return Err(ConfigError::XtalkDistanceOutOfRange{ mm }.into());
+        }
+
+        match unsafe { vl53l5cx_calibrate_xtalk(self.borrow_uld_mut(), refl as u16, samples, mm) } {
+            ST_OK => Ok(()),
+            e => Err(Error::from_uld(e))
+        }?;
+
+        #[cfg(feature = "defmt")]
+        debug!("Xtalk calibration done");
+        Ok(())
+    }
+
+    /*
+    * Read the xtalk data in use. Unless calibrated (or set), this is the vendor default.
+    */
+    pub fn xtalk_data(&mut self) -> Result<XtalkData> {
+        let mut buf = [0_u8; XTALK_SIZE];
+
+        match unsafe { vl53l5cx_get_caldata_xtalk(self.borrow_uld_mut(), buf.as_mut_ptr()) } {
+            ST_OK => Ok( XtalkData(buf) ),
+            e => Err(Error::from_uld(e))
+        }
+    }
+
+    /*
+    * Give earlier stored xtalk data to the sensor. Call after '.init()', before ranging.
+    */
+    pub fn set_xtalk_data(&mut self, data: &XtalkData) -> Result<()> {
+        let mut buf = data.0;   // C API takes '*mut'
+
+        match unsafe { vl53l5cx_set_caldata_xtalk(self.borrow_uld_mut(), buf.as_mut_ptr()) } {
+            ST_OK => Ok(()),
+            e => Err(Error::from_uld(e))
+        }
+    }
+
+    // tbd. 'vl53l5cx_{get|set}_xtalk_margin()' could be exposed, if someone needs them.
+}
@@ -0,0 +1,147 @@
+/*
+* Per-zone view over 'ResultsData'.
+*
+* 'ResultsData' keeps each field as its own matrix (the features decide, which ones exist). That
+* is compact, but consumers need to walk several of them in step. Here, a zone at a time, and the
+* fields of each target are collected into a 'Target'.
+*
+*   <<
+*       for z in res.zones() {
+*           for t in z.targets { ... t.distance_mm ... }
+*       }
+*   <<
+*
+* Zones come row by row; '(row, col)' index the matrices the same way as 'ResultsData' does.
+*/
+use crate::results_data::{
+    ResultsData,
+    TARGETS,
+};
+#[cfg(feature = "target_status")]
+use crate::results_data::TargetStatus;
+
+/*
+* One target (of a zone); whichever fields the features have enabled.
+*/
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Target {
+    pub index: usize,   // 0..TARGETS; order by 'RangingConfig::with_target_order()'
+    #[cfg(feature = "target_status")]
+    pub status: TargetStatus,
+    #[cfg(feature = "distance_mm")]
+    pub distance_mm: u16,
+    #[cfg(feature = "range_sigma_mm")]
+    pub range_sigma_mm: u16,
+    #[cfg(feature = "reflectance_percent")]
+    pub reflectance: u8,
+    #[cfg(feature = "signal_per_spad")]
+    pub signal_per_spad: u32,
+}
+
+#[cfg(feature = "target_status")]
+impl Target {
+    pub fn is_valid(&self) -> bool {
+        self.status.is_valid()
+    }
+}
+
+#[derive(Clone)]
+pub struct Zone<'a, const DIM: usize> {
+    pub row: usize,
+    pub col: usize,
+    #[cfg(feature = "nb_targets_detected")]
+    pub targets_detected: u8,
+    pub targets: Targets<'a, DIM>,
+}
+
+#[cfg(feature = "target_status")]
+impl<'a, const DIM: usize> Zone<'a, DIM> {
+    pub fn valid_targets(&self) -> impl Iterator<Item = Target> + 'a {
+        self.targets.clone().filter(Target::is_valid)
+    }
+}
+
+/*
+* Targets of a zone. With 'nb_targets_detected', only the detected ones; otherwise all 'TARGETS'.
+*/
+#[derive(Clone)]
+pub struct Targets<'a, const DIM: usize> {
+    res: &'a ResultsData<DIM>,
+    row: usize,
+    col: usize,
+    i: usize,
+    n: usize,
+}
+
+impl<const DIM: usize> Iterator for Targets<'_, DIM> {
+    type Item = Target;
+
+    fn next(&mut self) -> Option<Target> {
+        if self.i >= self.n { return None }
+
+        let (_r, _c, i, _res) = (self.row, self.col, self.i, self.res);
+        self.i += 1;
+
+        Some(Target{
+            index: i,
+            #[cfg(feature = "target_status")]
+            status: _res.target_status[i][_r][_c],
+            #[cfg(feature = "distance_mm")]
+            distance_mm: _res.distance_mm[i][_r][_c],
+            #[cfg(feature = "range_sigma_mm")]
+            range_sigma_mm: _res.range_sigma_mm[i][_r][_c],
+            #[cfg(feature = "reflectance_percent")]
+            reflectance: _res.reflectance[i][_r][_c],
+            #[cfg(feature = "signal_per_spad")]
+            signal_per_spad: _res.signal_per_spad[i][_r][_c],
+        })
+    }
+}
+
+pub struct Zones<'a, const DIM: usize> {
+    res: &'a ResultsData<DIM>,
+    next: usize,    // row*DIM + col
+}
+
+impl<'a, const DIM: usize> Iterator for Zones<'a, DIM> {
+    type Item = Zone<'a, DIM>;
+
+    fn next(&mut self) -> Option<Zone<'a, DIM>> {
+        if self.next >= DIM*DIM { return None }
+
+        let (row, col) = (self.next / DIM, self.next % DIM);
+        self.next += 1;
+
+        #[cfg(feature = "nb_targets_detected")]
+        let targets_detected = self.res.targets_detected[row][col];
+        #[cfg(feature = "nb_targets_detected")]
+        let n = (targets_detected as usize).min(TARGETS);
+        #[cfg(not(feature = "nb_targets_detected"))]
+        let n = TARGETS;
+
+        Some(Zone{
+            row, col,
+            #[cfg(feature = "nb_targets_detected")]
+            targets_detected,
+            targets: Targets{ res: self.res, row, col, i: 0, n },
+        })
+    }
+}
+
+impl<const DIM: usize> ResultsData<DIM> {
+    pub fn zones(&self) -> Zones<'_, DIM> {
+        Zones{ res: self, next: 0 }
+    }
+
+    /*
+    * All valid targets (as '(row, col, target)'), zone by zone.
+    */
+    #[cfg(feature = "target_status")]
+    pub fn valid_targets(&self) -> impl Iterator<Item = (usize, usize, Target)> + '_ {
+        self.zones().flat_map(|z| {
+            let (row, col) = (z.row, z.col);
+            z.targets.filter(Target::is_valid).map(move |t| (row, col, t))
+        })
+    }
+}
@@ -0,0 +1,160 @@
+/*
+* Host side tests of 'CalibrationBlob', against the simulated sensor.
+*
+*   $ cargo test --target x86_64-unknown-linux-gnu --features distance_mm --test calibration
+*/
+mod sim;
+
+use sim::Sim;
+use vl53l5cx_uld::{
+    CalibrationBlob,
+    CalibrationError,
+    RangingConfig,
+    State_HP_Idle,
+    VL53L5CX,
+    CALIBRATION_SIZE,
+};
+
+const XTALK_AT: usize = 8 + 488;
+
+// A sensor with its own NVM contents
+fn sensor(seed: u8) -> Sim {
+    let sim = Sim::flat(1000);
+    let nvm: Vec<u8> = (0..492).map(|i| (i as u8).wrapping_mul(seed)).collect();
+    sim.set_nvm(&nvm);
+    sim
+}
+
+fn init(sim: &Sim) -> State_HP_Idle {
+    VL53L5CX::new_with_ping(sim.platform()).unwrap().init().unwrap()
+}
+
+// Start an 8x8 ranging (which sends the calibration), and see what was sent: '(offset, xtalk)'
+fn uploads(sim: &Sim, vl: State_HP_Idle) -> (Vec<u8>, Vec<u8>) {
+    let r = vl.start_ranging(&RangingConfig::<8>::default()).unwrap();
+    r.stop().unwrap();
+    (sim.offset_upload(), sim.xtalk_upload())
+}
+
+// CRC-32 (IEEE), written independently of the library
+fn crc32(bs: &[u8]) -> u32 {
+    let table: Vec<u32> = (0..256_u32).map(|mut c| {
+        for _ in 0..8 { c = if c & 1 == 1 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 } }
+        c
+    }).collect();
+    !bs.iter().fold(!0_u32, |c, b| table[((c ^ *b as u32) & 0xff) as usize] ^ (c >> 8))
+}
+
+#[test]
+fn blob_format() {
+    let sim = sensor(3);
+    let bs = init(&sim).calibration().as_bytes().to_vec();
+
+    assert_eq!(bs.len(), CALIBRATION_SIZE);
+    assert_eq!(&bs[..5], b"VLCB\x01");
+    assert_eq!(bs[CALIBRATION_SIZE-4..], crc32(&bs[..CALIBRATION_SIZE-4]).to_le_bytes());
+}
+
+#[test]
+fn round_trip() {
+    let blob = init(&sensor(3)).calibration();
+    let back = CalibrationBlob::try_from(&blob.as_bytes()[..]).unwrap();
+
+    assert_eq!(back.as_bytes(), blob.as_bytes());
+}
+
+#[test]
+fn offsets_differ_by_sensor() {
+    let (a, b) = (sensor(3), sensor(7));
+    let (oa, _) = { let vl = init(&a); uploads(&a, vl) };
+    let (ob, _) = { let vl = init(&b); uploads(&b, vl) };
+
+    assert_ne!(oa, ob);
+}
+
+#[test]
+fn applied_blob_is_used() {
+    let a = sensor(3);
+    let vl = init(&a);
+    let blob = vl.calibration();
+    let expected = uploads(&a, vl);
+
+    // Another sensor, told to use the first one's calibration
+    let b = sensor(7);
+    let mut vl = init(&b);
+    vl.set_calibration(&blob).unwrap();
+
+    assert_eq!(uploads(&b, vl), expected);
+}
+
+#[test]
+fn applied_blob_is_sent_right_away() {
+    let a = sensor(3);
+    let mut vl = init(&a);
+    let blob = vl.calibration();
+    vl.set_calibration(&blob).unwrap();
+    let expected = (a.offset_upload(), a.xtalk_upload());
+
+    let b = sensor(7);
+    let mut vl = init(&b);
+    let n = b.accesses();
+    vl.set_calibration(&blob).unwrap();
+
+    assert!(b.accesses() > n);
+    assert_eq!((b.offset_upload(), b.xtalk_upload()), expected);
+}
+
+#[test]
+fn failed_send_is_an_error() {
+    let blob = init(&sensor(3)).calibration();
+
+    let b = sensor(7);
+    let mut vl = init(&b);
+    b.nack_after(0);
+    assert!(vl.set_calibration(&blob).is_err());
+}
+
+#[test]
+fn applied_xtalk_is_used() {
+    let sim = sensor(3);
+    let vl = init(&sim);
+    let mut bs = vl.calibration().as_bytes().to_vec();
+    let (_, default_xtalk) = uploads(&sim, vl);
+
+    // As if calibrated
+    for b in &mut bs[XTALK_AT..XTALK_AT+100] { *b ^= 0x5a; }
+    let n = bs.len();
+    let crc = crc32(&bs[..n-4]);
+    bs[n-4..].copy_from_slice(&crc.to_le_bytes());
+
+    let blob = CalibrationBlob::try_from(&bs[..]).unwrap();
+    let mut vl = init(&sim);
+    vl.set_calibration(&blob).unwrap();
+
+    let (_, xtalk) = uploads(&sim, vl);
+    assert_ne!(xtalk, default_xtalk);
+}
+
+#[test]
+fn corrupted_blob_is_rejected() {
+    let blob = init(&sensor(3)).calibration();
+    let mut bs = blob.as_bytes().to_vec();
+
+    bs[100] ^= 0x01;
+    assert_eq!(CalibrationBlob::try_from(&bs[..]).err(), Some(CalibrationError::Crc));
+}
+
+#[test]
+fn wrong_blobs_are_rejected() {
+    let bs = init(&sensor(3)).calibration().as_bytes().to_vec();
+
+    assert_eq!(CalibrationBlob::try_from(&bs[..100]).err(), Some(CalibrationError::Size{ n: 100 }));
+
+    let mut x = bs.clone();
+    x[0] = b'X';
+    assert_eq!(CalibrationBlob::try_from(&x[..]).err(), Some(CalibrationError::Magic));
+
+    let mut x = bs.clone();
+    x[4] = 2;
+    assert_eq!(CalibrationBlob::try_from(&x[..]).err(), Some(CalibrationError::Version{ v: 2 }));
+}
@@ -0,0 +1,117 @@
+/*
+* Host side tests of detection thresholds ('ThresholdsConfig'), against the simulated sensor: what
+* gets programmed, zone numbering under an 'Orientation', and the config checks.
+*
+*   $ cargo test --target x86_64-unknown-linux-gnu --features distance_mm,detection_thresholds --test detection_thresholds
+*/
+mod sim;
+
+use sim::Sim;
+use vl53l5cx_uld::{
+    Combine,
+    ConfigError,
+    Error,
+    Measurement,
+    Orientation,
+    RangingConfig,
+    State_HP_Idle,
+    ThresholdsConfig,
+    Window,
+    VL53L5CX,
+};
+
+const LAST: u8 = 0x80;      // "last threshold" marker, in 'zone_num'
+
+fn init(sim: &Sim) -> State_HP_Idle {
+    VL53L5CX::new_with_ping(sim.platform()).unwrap().init().unwrap()
+}
+
+fn near(zone: u8) -> ThresholdsConfig {
+    ThresholdsConfig::new()
+        .with(zone, Measurement::DistanceMm, Window::AtMost(500), Combine::Or).unwrap()
+}
+
+#[test]
+fn thresholds_go_to_the_sensor() {
+    let sim = Sim::flat(1000);
+    let th = near(5)
+        .with(5, Measurement::TargetStatus, Window::Equal(5), Combine::And).unwrap();
+
+    let r = init(&sim).start_ranging(&RangingConfig::<4>::default().with_thresholds(th)).unwrap();
+    assert!(sim.thresholds_enabled());
+
+    let v = sim.thresholds();
+    assert_eq!(v.len(), 2);
+    assert_eq!(v[0].zone_num, 5);
+    assert_eq!(v[0].low, 500*4);                // vendor plugin scales mm to the sensor's units
+    assert_eq!(v[1].zone_num, 5 | LAST);
+    assert_eq!(v[1].low, 5);
+    assert_ne!(v[0].measurement, v[1].measurement);
+    assert_ne!(v[0].op, v[1].op);
+
+    r.stop().unwrap();
+}
+
+#[test]
+fn zones_follow_the_orientation() {
+    for (o, zone, exp) in [
+        (Orientation::Upright, 1, 1),
+        (Orientation::Rotated180, 1, 14),       // (0,1) -> sensor (3,2)
+        (Orientation::Rotated180, 0, 15),
+        (Orientation::Mirrored, 1, 2),          // (0,1) -> sensor (0,2)
+    ] {
+        let sim = Sim::flat(1000);
+        let cfg = RangingConfig::<4>::default().with_orientation(o).with_thresholds(near(zone));
+        let _r = init(&sim).start_ranging(&cfg).unwrap();
+
+        assert_eq!(sim.thresholds()[0].zone_num, exp | LAST, "{:?}", o);
+    }
+}
+
+#[test]
+fn zones_follow_the_orientation_8x8() {
+    let sim = Sim::flat(1000);
+    let cfg = RangingConfig::<8>::default()
+        .with_orientation(Orientation::Mirrored)
+        .with_thresholds(near(9));      // (1,1) -> sensor (1,6)
+    let _r = init(&sim).start_ranging(&cfg).unwrap();
+
+    assert_eq!(sim.thresholds()[0].zone_num, 14 | LAST);
+}
+
+#[test]
+fn turned_off_when_not_given() {
+    // The sensor keeps thresholds over rangings; a ranging without them must turn them off.
+    let sim = Sim::flat(1000);
+    let r = init(&sim).start_ranging(&RangingConfig::<4>::default().with_thresholds(near(0))).unwrap();
+    assert!(sim.thresholds_enabled());
+
+    let vl = r.stop().unwrap();
+    let _r = vl.start_ranging(&RangingConfig::<4>::default()).unwrap();
+    assert!(!sim.thresholds_enabled());
+}
+
+#[test]
+fn at_most_64() {
+    let mut th = ThresholdsConfig::new();
+    for i in 0..64 {
+        th = th.with(i % 16, Measurement::DistanceMm, Window::AtMost(500), Combine::Or).unwrap();
+    }
+
+    let e = th.with(0, Measurement::DistanceMm, Window::AtMost(500), Combine::Or).err();
+    assert_eq!(e, Some(ConfigError::TooManyThresholds{ max: 64 }));
+}
+
+#[test]
+fn checked_before_sending() {
+    for (th, e) in [
+        (ThresholdsConfig::new(), ConfigError::NoThresholds),
+        (near(16), ConfigError::ThresholdZoneOutOfRange{ zone: 16, zones: 16 }),
+    ] {
+        let sim = Sim::flat(1000);
+        let cfg = RangingConfig::<4>::default().with_thresholds(th);
+
+        assert_eq!(init(&sim).start_ranging(&cfg).err(), Some(Error::Config(e)));
+        assert!(sim.thresholds().is_empty());
+    }
+}
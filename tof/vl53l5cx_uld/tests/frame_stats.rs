@@ -0,0 +1,115 @@
+/*
+* Host side tests of 'FrameStats' (frame counter tracking), against the simulated sensor.
+*
+*   $ cargo test --target x86_64-unknown-linux-gnu --features distance_mm --test frame_stats
+*/
+mod sim;
+
+use sim::Sim;
+use vl53l5cx_uld::{
+    FrameStats,
+    RangingConfig,
+    State_Ranging,
+    VL53L5CX,
+};
+
+fn start(sim: &Sim) -> State_Ranging<4> {
+    VL53L5CX::new_with_ping(sim.platform()).unwrap()
+        .init().unwrap()
+        .start_ranging(&RangingConfig::<4>::default()).unwrap()
+}
+
+// The normal way: see that there's a frame, then read it.
+fn next(r: &mut State_Ranging<4>) -> u8 {
+    assert!(r.is_ready().unwrap());
+    r.get_data().unwrap();
+    r.streamcount()
+}
+
+fn counts(st: &FrameStats) -> (u32, u32, u32, u32, u32) {
+    (st.frames, st.gaps, st.dropped, st.duplicates, st.stale)
+}
+
+#[test]
+fn steady_stream() {
+    let sim = Sim::flat(1000);
+    let mut r = start(&sim);
+
+    let mut prev = next(&mut r);
+    for _ in 0..9 {
+        let c = next(&mut r);
+        assert_eq!(c, (prev + 1) % 255);
+        prev = c;
+    }
+    assert_eq!(counts(r.frame_stats()), (10, 0, 0, 0, 0));
+    assert_eq!(r.frame_stats().delivery_ratio(), 1.0);
+}
+
+#[test]
+fn counter_wraps_without_gaps() {
+    let sim = Sim::flat(1000);
+    let mut r = start(&sim);
+
+    let cs: Vec<u8> = (0..300).map(|_| next(&mut r)).collect();
+
+    assert!(cs.iter().all(|c| *c != 255));
+    assert!(cs.windows(2).any(|w| w[1] < w[0]));     // did wrap
+    assert_eq!(counts(r.frame_stats()), (300, 0, 0, 0, 0));
+}
+
+#[test]
+fn missed_frames_are_counted() {
+    let sim = Sim::flat(1000);
+    let mut r = start(&sim);
+
+    next(&mut r);
+    sim.produce_frames(3);      // not looked at
+    next(&mut r);
+    next(&mut r);
+    sim.produce_frames(1);
+    next(&mut r);
+
+    assert_eq!(counts(r.frame_stats()), (4, 2, 4, 0, 0));
+    assert_eq!(r.frame_stats().delivery_ratio(), 0.5);
+}
+
+#[test]
+fn reading_again_is_a_duplicate() {
+    let sim = Sim::flat(1000);
+    let mut r = start(&sim);
+
+    let a = next(&mut r);
+    r.get_data().unwrap();      // without a new frame
+    assert_eq!(r.streamcount(), a);
+
+    assert_eq!(counts(r.frame_stats()), (2, 0, 0, 1, 0));
+}
+
+#[test]
+fn newer_than_seen_is_stale() {
+    let sim = Sim::flat(1000);
+    let mut r = start(&sim);
+
+    next(&mut r);
+    assert!(r.is_ready().unwrap());
+    sim.produce_frames(1);      // made while the seen one waited to be read
+    r.get_data().unwrap();
+
+    // The seen frame was never read: it's also a gap
+    assert_eq!(counts(r.frame_stats()), (2, 1, 1, 0, 1));
+}
+
+#[test]
+fn new_ranging_starts_over() {
+    let sim = Sim::flat(1000);
+    let mut r = start(&sim);
+    next(&mut r);
+    next(&mut r);
+
+    let vl = r.stop().unwrap();
+    sim.produce_frames(5);
+    let mut r = vl.start_ranging(&RangingConfig::<4>::default()).unwrap();
+    next(&mut r);
+
+    assert_eq!(counts(r.frame_stats()), (1, 0, 0, 0, 0));
+}
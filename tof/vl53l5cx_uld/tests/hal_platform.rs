@@ -0,0 +1,78 @@
+/*
+* Host side tests of 'HalPlatform' against 'embedded-hal-mock'.
+*
+*   $ cargo test --target x86_64-unknown-linux-gnu --features embedded_hal --test hal_platform
+*/
+use embedded_hal::i2c::ErrorKind;
+use embedded_hal_mock::eh1::{
+    delay::NoopDelay,
+    i2c::{Mock as I2cMock, Transaction as T},
+};
+use vl53l5cx_uld::{HalPlatform, I2cAddr, Platform};
+
+const A: u8 = 0x29;     // 'DEFAULT_I2C_ADDR', as 7-bit
+
+#[test]
+fn write_prefixes_index() {
+    let mut i2c = I2cMock::new(&[
+        T::write(A, vec![0x7f, 0xff, 0x02]),
+    ]);
+    let mut pl: HalPlatform<_,_> = HalPlatform::new(i2c.clone(), NoopDelay);
+
+    pl.wr_bytes(0x7fff, &[0x02]).unwrap();
+    i2c.done();
+}
+
+#[test]
+fn write_is_chunked() {
+    // MAX=6 -> 4 data bytes per transaction; index rolls with the chunks
+    let mut i2c = I2cMock::new(&[
+        T::write(A, vec![0x10, 0x00, 1,2,3,4]),
+        T::write(A, vec![0x10, 0x04, 5,6,7,8]),
+        T::write(A, vec![0x10, 0x08, 9]),
+    ]);
+    let mut pl: HalPlatform<_,_,6> = HalPlatform::new(i2c.clone(), NoopDelay);
+
+    pl.wr_bytes(0x1000, &[1,2,3,4,5,6,7,8,9]).unwrap();
+    i2c.done();
+}
+
+#[test]
+fn read_is_chunked() {
+    let mut i2c = I2cMock::new(&[
+        T::write_read(A, vec![0x20, 0x00], vec![1,2,3,4,5,6]),
+        T::write_read(A, vec![0x20, 0x06], vec![7,8]),
+    ]);
+    let mut pl: HalPlatform<_,_,6> = HalPlatform::new(i2c.clone(), NoopDelay);
+
+    let mut buf = [0_u8; 8];
+    pl.rd_bytes(0x2000, &mut buf).unwrap();
+    assert_eq!(buf, [1,2,3,4,5,6,7,8]);
+    i2c.done();
+}
+
+#[test]
+fn follows_addr_change() {
+    let b = I2cAddr::from_7bit(0x30);
+    let mut i2c = I2cMock::new(&[
+        T::write(A, vec![0x00, 0x04, 0x30]),
+        T::write(0x30, vec![0x7f, 0xff, 0x02]),
+    ]);
+    let mut pl: HalPlatform<_,_> = HalPlatform::new(i2c.clone(), NoopDelay);
+
+    pl.wr_bytes(0x0004, &[0x30]).unwrap();
+    pl.addr_changed(&b);
+    pl.wr_bytes(0x7fff, &[0x02]).unwrap();
+    i2c.done();
+}
+
+#[test]
+fn error_is_returned() {
+    let mut i2c = I2cMock::new(&[
+        T::write(A, vec![0x00, 0x00, 0xaa]).with_error(ErrorKind::Other),
+    ]);
+    let mut pl: HalPlatform<_,_> = HalPlatform::new(i2c.clone(), NoopDelay);
+
+    assert!(pl.wr_bytes(0x0000, &[0xaa]).is_err());
+    i2c.done();
+}
@@ -0,0 +1,71 @@
+/*
+* Host side tests of the driver's first contact with the sensor ('new_with_ping()'), using a
+* mock 'Platform'.
+*
+*   $ cargo test --target x86_64-unknown-linux-gnu --test host_ping
+*/
+use vl53l5cx_uld::{Error, I2cAddr, Platform, VL53L5CX};
+
+/*
+* Answers the page select (0x7fff) and the (device id, revision id) read at 0x0000 (page 0).
+*/
+struct MockPl {
+    ids: (u8,u8),
+    fail: bool,     // all I2C fails
+    page: u8,
+}
+
+impl MockPl {
+    fn new(ids: (u8,u8)) -> Self {
+        Self{ ids, fail: false, page: 2 }
+    }
+}
+
+impl Platform for MockPl {
+    fn rd_bytes(&mut self, index: u16, buf: &mut [u8]) -> Result<(),()> {
+        if self.fail { return Err(()) }
+
+        match (self.page, index) {
+            (0, 0) => {
+                buf[0] = self.ids.0;
+                if buf.len() > 1 { buf[1] = self.ids.1 };
+            },
+            _ => buf.fill(0)
+        }
+        Ok(())
+    }
+
+    fn wr_bytes(&mut self, index: u16, vs: &[u8]) -> Result<(),()> {
+        if self.fail { return Err(()) }
+
+        if index == 0x7fff { self.page = vs[0] };
+        Ok(())
+    }
+
+    fn delay_ms(&mut self, _ms: u32) {}
+
+    fn addr_changed(&mut self, _addr: &I2cAddr) {}
+}
+
+#[test]
+fn ping_ok() {
+    assert!(VL53L5CX::new_with_ping(MockPl::new((0xf0, 0x02))).is_ok());
+}
+
+#[test]
+fn ping_mismatch() {
+    match VL53L5CX::new_with_ping(MockPl::new((0xf0, 0x01))) {
+        Err(e) => assert_eq!(e, Error::PingMismatch{ dev_id: 0xf0, rev_id: 0x01 }),
+        Ok(_) => panic!("ping should fail")
+    }
+}
+
+#[test]
+fn ping_i2c_fails() {
+    let pl = MockPl{ fail: true, ..MockPl::new((0xf0, 0x02)) };
+
+    match VL53L5CX::new_with_ping(pl) {
+        Err(e) => assert_eq!(e, Error::PlatformI2c),
+        Ok(_) => panic!("ping should fail")
+    }
+}
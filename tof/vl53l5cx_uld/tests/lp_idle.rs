@@ -0,0 +1,72 @@
+/*
+* Host side tests of 'State_LP_Idle': sleep / wake round trips, and retrying a failed transition,
+* against the simulated sensor.
+*
+*   $ cargo test --target x86_64-unknown-linux-gnu --features distance_mm --test lp_idle
+*/
+mod sim;
+
+use sim::Sim;
+use vl53l5cx_uld::{
+    I2cAddr,
+    RangingConfig,
+    State_HP_Idle,
+    VL53L5CX,
+};
+
+fn init(sim: &Sim) -> State_HP_Idle {
+    VL53L5CX::new_with_ping(sim.platform()).unwrap().init().unwrap()
+}
+
+#[test]
+fn round_trip_keeps_the_setup() {
+    let sim = Sim::flat(1000);
+    let mut vl = init(&sim);
+    vl.set_i2c_address(&I2cAddr::from_7bit(0x30)).unwrap();
+
+    for _ in 0..2 {
+        let lp = vl.go_to_sleep().unwrap();
+        assert!(sim.is_asleep());
+
+        vl = lp.wake_up().unwrap();
+        assert!(!sim.is_asleep());
+    }
+
+    // Firmware and address are as they were; ranging works without a new '.init()'
+    assert_eq!(sim.dev_addr(), 0x30);
+    assert!(sim.fw_uploaded());
+
+    let mut r = vl.start_ranging(&RangingConfig::<4>::default()).unwrap();
+    assert!(r.is_ready().unwrap());
+    let (res, _) = r.get_data().unwrap();
+    assert_eq!(res.distance_mm[0][0][0], 1000);
+}
+
+#[test]
+fn failed_sleep_gives_the_sensor_back() {
+    let sim = Sim::flat(1000);
+    let vl = init(&sim);
+
+    sim.nack_after(0);
+    let (vl, _e) = vl.go_to_sleep().unwrap_err();
+
+    // Try again
+    let lp = vl.go_to_sleep().unwrap();
+    assert!(sim.is_asleep());
+    let _vl = lp.wake_up().unwrap();
+}
+
+#[test]
+fn failed_wake_up_can_be_retried() {
+    let sim = Sim::flat(1000);
+    let lp = init(&sim).go_to_sleep().unwrap();
+
+    sim.nack_after(0);
+    let (lp, _e) = lp.wake_up().unwrap_err();
+
+    let vl = lp.wake_up().unwrap();
+    assert!(!sim.is_asleep());
+
+    let mut r = vl.start_ranging(&RangingConfig::<4>::default()).unwrap();
+    assert!(r.is_ready().unwrap());
+}
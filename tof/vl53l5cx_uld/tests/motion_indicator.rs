@@ -0,0 +1,109 @@
+/*
+* Host side tests of the motion indicator ('MotionConfig', 'ResultsData::motion'), against the
+* simulated sensor.
+*
+*   $ cargo test --target x86_64-unknown-linux-gnu --features distance_mm,motion_indicator --test motion_indicator
+*/
+mod sim;
+
+use sim::{Sim, SimTarget, SimZone};
+use vl53l5cx_uld::{
+    ConfigError,
+    Error,
+    MotionConfig,
+    Orientation,
+    RangingConfig,
+    ResultsData,
+    State_HP_Idle,
+    VL53L5CX,
+};
+
+const DCI_MOTION_DETECTOR_CFG: u16 = 0xbfac;
+
+fn init(sim: &Sim) -> State_HP_Idle {
+    VL53L5CX::new_with_ping(sim.platform()).unwrap().init().unwrap()
+}
+
+/*
+* Motion at the zones (sensor order) picked by 'f'.
+*/
+fn sim_with_motion(f: impl Fn(usize) -> u32 + 'static) -> Sim {
+    Sim::new(Box::new(move |_, z, _| SimZone{
+        targets: vec![SimTarget::at(1500)],
+        motion: f(z),
+        ..SimZone::default()
+    }))
+}
+
+fn run<const DIM: usize>(sim: &Sim, cfg: &RangingConfig<DIM>) -> ResultsData<DIM> {
+    let mut r = init(sim).start_ranging(cfg).unwrap();
+    assert!(r.is_ready().unwrap());
+    r.get_data().unwrap().0
+}
+
+#[test]
+fn motion_per_zone_4x4() {
+    // At 4x4, each zone is an aggregate of its own.
+    let sim = sim_with_motion(|z| 10*z as u32 + 1);
+    let res = run(&sim, &RangingConfig::<4>::default());
+
+    for r in 0..4 {
+        for c in 0..4 {
+            assert_eq!(res.motion[r][c], 10*(4*r + c) as u32 + 1, "({},{})", r, c);
+        }
+    }
+}
+
+#[test]
+fn zones_share_aggregates_at_8x8() {
+    // At 8x8, each 2x2 block of zones is one aggregate. Zone 9 is (1,1); the top left block.
+    let sim = sim_with_motion(|z| if z == 9 { 1000 } else { 0 });
+    let res = run(&sim, &RangingConfig::<8>::default());
+
+    for r in 0..8 {
+        for c in 0..8 {
+            let exp = if r < 2 && c < 2 { 1000 } else { 0 };
+            assert_eq!(res.motion[r][c], exp, "({},{})", r, c);
+        }
+    }
+}
+
+#[test]
+fn motion_is_turned_like_the_rest() {
+    let sim = sim_with_motion(|z| if z == 0 { 77 } else { 0 });
+    let res = run(&sim, &RangingConfig::<4>::default().with_orientation(Orientation::Rotated180));
+
+    assert_eq!(res.motion[3][3], 77);
+    assert_eq!(res.motion[0][0], 0);
+}
+
+#[test]
+fn window_goes_to_the_sensor() {
+    let sim = Sim::flat(1500);
+    let cfg = RangingConfig::<4>::default().with_motion(MotionConfig::new(1000, 2000));
+    let _ = run(&sim, &cfg);
+
+    // Vendor conversions ('vl53l5cx_motion_indicator_set_distance_motion()')
+    let bs = sim.dci(DCI_MOTION_DETECTOR_CFG, 28);
+    let ref_bin_offset = i32::from_le_bytes(bs[0..4].try_into().unwrap());
+    let feature_length = bs[19];
+
+    let exp = ((1000.0_f32 / 37.5348) - 4.0) * 2048.5;
+    assert!((ref_bin_offset as f32 - exp).abs() <= 1.0, "{}", ref_bin_offset);
+    assert_eq!(feature_length, 9);
+}
+
+#[test]
+fn window_checked_before_sending() {
+    for (mc, e) in [
+        (MotionConfig::new(300, 1000), ConfigError::MotionDistanceOutOfRange{ mm: 300 }),
+        (MotionConfig::new(2000, 1000), ConfigError::MotionWindowEmpty{ min_mm: 2000, max_mm: 1000 }),
+        (MotionConfig::new(1000, 3000), ConfigError::MotionWindowTooWide{ width_mm: 2000 }),
+    ] {
+        let sim = Sim::flat(1500);
+        let cfg = RangingConfig::<4>::default().with_motion(mc);
+
+        assert_eq!(init(&sim).start_ranging(&cfg).err(), Some(Error::Config(e)));
+        assert!(!sim.is_ranging());
+    }
+}
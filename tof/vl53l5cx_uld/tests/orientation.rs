@@ -0,0 +1,125 @@
+/*
+* Host side tests of 'RangingConfig::with_orientation()', against the simulated sensor. All eight
+* orientations, both resolutions.
+*
+*   $ cargo test --target x86_64-unknown-linux-gnu --features distance_mm --test orientation
+*/
+mod sim;
+
+use sim::{Sim, SimTarget, SimZone};
+use vl53l5cx_uld::{
+    Orientation,
+    RangingConfig,
+    ResultsData,
+    TargetStatus,
+    VL53L5CX,
+};
+
+type M<const DIM: usize> = [[u16; DIM]; DIM];
+
+/*
+* The sensor sees 'distance = 100*row + col + 1' at each zone, and only zone (0,0) is valid.
+*/
+fn sensor<const DIM: usize>() -> M<DIM> {
+    core::array::from_fn(|r| core::array::from_fn(|c| (100*r + c + 1) as u16))
+}
+
+fn run<const DIM: usize>(o: Orientation) -> ResultsData<DIM> {
+    let sim = Sim::new(Box::new(|_, z, zones| {
+        let dim = if zones == 64 { 8 } else { 4 };
+        let (r, c) = (z / dim, z % dim);
+        SimZone{
+            targets: vec![SimTarget{ status: if z == 0 { 5 } else { 3 }, ..SimTarget::at((100*r + c + 1) as u16) }],
+            ..SimZone::default()
+        }
+    }));
+    let vl = VL53L5CX::new_with_ping(sim.platform()).unwrap().init().unwrap();
+    let mut r = vl.start_ranging(&RangingConfig::<DIM>::default().with_orientation(o)).unwrap();
+
+    assert!(r.is_ready().unwrap());
+    r.get_data().unwrap().0
+}
+
+// Turn a matrix 90° clockwise.
+fn cw<const DIM: usize>(m: &M<DIM>) -> M<DIM> {
+    core::array::from_fn(|r| core::array::from_fn(|c| m[DIM-1-c][r]))
+}
+
+// Swap left and right.
+fn mirror<const DIM: usize>(m: &M<DIM>) -> M<DIM> {
+    core::array::from_fn(|r| core::array::from_fn(|c| m[r][DIM-1-c]))
+}
+
+fn expected<const DIM: usize>(o: Orientation) -> M<DIM> {
+    use Orientation::*;
+    let s = sensor::<DIM>();
+
+    let (turns, mirrored) = match o {
+        Upright => (0, false),
+        Rotated90 => (1, false),
+        Rotated180 => (2, false),
+        Rotated270 => (3, false),
+        Mirrored => (0, true),
+        MirroredRotated90 => (1, true),
+        MirroredRotated180 => (2, true),
+        MirroredRotated270 => (3, true),
+    };
+    let m = (0..turns).fold(s, |m, _| cw(&m));
+    if mirrored { mirror(&m) } else { m }
+}
+
+fn check<const DIM: usize>() {
+    for o in Orientation::ALL {
+        let res = run::<DIM>(o);
+        let exp = expected::<DIM>(o);
+
+        assert_eq!(res.distance_mm[0], exp, "{:?} ({}x{})", o, DIM, DIM);
+
+        // Status goes along with the distance
+        for (r, row) in exp.iter().enumerate() {
+            for (c, &v) in row.iter().enumerate() {
+                let valid = v == 1;
+                assert_eq!(res.target_status[0][r][c] == TargetStatus::Valid, valid, "{:?} ({},{})", o, r, c);
+            }
+        }
+    }
+}
+
+#[test]
+fn all_orientations_4x4() {
+    check::<4>();
+}
+
+#[test]
+fn all_orientations_8x8() {
+    check::<8>();
+}
+
+#[test]
+fn upright_is_default() {
+    assert_eq!(run::<4>(Orientation::default()).distance_mm[0], sensor::<4>());
+}
+
+#[test]
+fn rotations_by_example() {
+    // Spot checks, not derived from 'cw()': the sensor's top-left zone (distance 1) moves to..
+    let at = |o| {
+        let m = run::<4>(o).distance_mm[0];
+        (0..16).map(|i| (i/4, i%4)).find(|&(r,c)| m[r][c] == 1).unwrap()
+    };
+    assert_eq!(at(Orientation::Rotated90), (0,3));
+    assert_eq!(at(Orientation::Rotated180), (3,3));
+    assert_eq!(at(Orientation::Rotated270), (3,0));
+    assert_eq!(at(Orientation::Mirrored), (0,3));
+    assert_eq!(at(Orientation::MirroredRotated90), (0,0));
+}
+
+#[test]
+fn all_distinct() {
+    let ms: Vec<_> = Orientation::ALL.iter().map(|o| expected::<4>(*o)).collect();
+    for i in 0..8 {
+        for j in i+1..8 {
+            assert_ne!(ms[i], ms[j]);
+        }
+    }
+}
@@ -0,0 +1,293 @@
+/*
+* Host side tests of 'ConfigRecord', against an in-memory flash.
+*
+*   $ cargo test --target x86_64-unknown-linux-gnu --features persist --test persist
+*/
+mod sim;
+
+use embedded_storage::nor_flash::{
+    ErrorType,
+    NorFlash,
+    NorFlashError,
+    NorFlashErrorKind,
+    ReadNorFlash,
+};
+
+use sim::Sim;
+use vl53l5cx_uld::{
+    units::{HzU8, MsU16, PrcU8},
+    BoardConfig,
+    ConfigRecord,
+    I2cAddr,
+    Mode,
+    Orientation,
+    PersistError,
+    RangingConfig,
+    RangingConfigDyn,
+    Resolution,
+    VL53L5CX,
+};
+
+/*
+* NOR flash stand-in: erase sets to 0xff; writes can only clear bits; alignments are checked.
+*/
+struct MemFlash {
+    mem: Vec<u8>,
+    erases: usize,
+}
+
+#[derive(Debug)]
+struct MemFlashError(NorFlashErrorKind);
+
+impl NorFlashError for MemFlashError {
+    fn kind(&self) -> NorFlashErrorKind { self.0 }
+}
+
+impl MemFlash {
+    fn new(size: usize) -> Self {
+        Self{ mem: vec![0xff; size], erases: 0 }
+    }
+
+    fn check(&self, at: u32, n: usize, align: usize) -> Result<(), MemFlashError> {
+        if at as usize % align != 0 || n % align != 0 { return Err(MemFlashError(NorFlashErrorKind::NotAligned)) }
+        if at as usize + n > self.mem.len() { return Err(MemFlashError(NorFlashErrorKind::OutOfBounds)) }
+        Ok(())
+    }
+}
+
+impl ErrorType for MemFlash {
+    type Error = MemFlashError;
+}
+
+impl ReadNorFlash for MemFlash {
+    const READ_SIZE: usize = 4;
+
+    fn read(&mut self, at: u32, bs: &mut [u8]) -> Result<(), Self::Error> {
+        self.check(at, bs.len(), Self::READ_SIZE)?;
+        let i = at as usize;
+        bs.copy_from_slice(&self.mem[i..i+bs.len()]);
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize { self.mem.len() }
+}
+
+impl NorFlash for MemFlash {
+    const WRITE_SIZE: usize = 4;
+    const ERASE_SIZE: usize = 4096;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        self.check(from, (to-from) as usize, Self::ERASE_SIZE)?;
+        self.mem[from as usize..to as usize].fill(0xff);
+        self.erases += 1;
+        Ok(())
+    }
+
+    fn write(&mut self, at: u32, bs: &[u8]) -> Result<(), Self::Error> {
+        self.check(at, bs.len(), Self::WRITE_SIZE)?;
+        for (i,b) in bs.iter().enumerate() {
+            self.mem[at as usize + i] &= *b;    // NOR: only 1 -> 0
+        }
+        Ok(())
+    }
+}
+
+const AT: u32 = 0x2000;
+
+fn record() -> ConfigRecord<3> {
+    let sim = Sim::flat(1000);
+    let cal = VL53L5CX::new_with_ping(sim.platform()).unwrap().init().unwrap().calibration();
+
+    ConfigRecord{ boards: [
+        BoardConfig{
+            i2c_addr: I2cAddr::from_7bit(0x30),
+            cfg: RangingConfig::<4>::default().with_orientation(Orientation::Rotated90).into(),
+            calibration: Some(cal),
+        },
+        BoardConfig{
+            i2c_addr: I2cAddr::from_7bit(0x31),
+            cfg: RangingConfig::<8>::default()
+                .with_mode(Mode::AUTONOMOUS(MsU16(20), HzU8(10)))
+                .with_sharpener(PrcU8(20))
+                .into(),
+            calibration: None,
+        },
+        BoardConfig{
+            i2c_addr: I2cAddr::from_7bit(0x32),
+            cfg: RangingConfig::<4>::default().with_mode(Mode::CONTINUOUS).into(),
+            calibration: None,
+        },
+    ]}
+}
+
+fn stored() -> MemFlash {
+    let mut f = MemFlash::new(0x8000);
+    record().store(&mut f, AT).unwrap();
+    f
+}
+
+// CRC-32 (IEEE), written independently of the library
+fn crc32(bs: &[u8]) -> u32 {
+    let table: Vec<u32> = (0..256_u32).map(|mut c| {
+        for _ in 0..8 { c = if c & 1 == 1 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 } }
+        c
+    }).collect();
+    !bs.iter().fold(!0_u32, |c, b| table[((c ^ *b as u32) & 0xff) as usize] ^ (c >> 8))
+}
+
+// Change the record's header (after it's been stored), keeping it intact
+fn restamp(f: &mut MemFlash, edit: impl FnOnce(&mut [u8])) {
+    let at = AT as usize;
+    edit(&mut f.mem[at..at+8]);
+
+    let len = u16::from_le_bytes([f.mem[at+6], f.mem[at+7]]) as usize;
+    let crc = crc32(&f.mem[at..at+8+len]);
+    f.mem[at+8+len..at+8+len+4].copy_from_slice(&crc.to_le_bytes());
+}
+
+fn defaults(_: PersistError) -> ConfigRecord<3> {
+    ConfigRecord{ boards: core::array::from_fn(|i| BoardConfig{
+        i2c_addr: I2cAddr::from_7bit(0x40 + i as u8),
+        cfg: RangingConfig::<4>::default().into(),
+        calibration: None,
+    })}
+}
+
+#[test]
+fn round_trip() {
+    let mut f = stored();
+    let r = ConfigRecord::<3>::load(&mut f, AT).unwrap();
+    let orig = record();
+
+    for (a, b) in r.boards.iter().zip(orig.boards.iter()) {
+        assert!(a.i2c_addr == b.i2c_addr);
+        assert_eq!(a.cfg.resolution(), b.cfg.resolution());
+        assert_eq!(a.calibration.as_ref().map(|c| c.as_bytes().to_vec()), b.calibration.as_ref().map(|c| c.as_bytes().to_vec()));
+    }
+    assert_eq!(r.boards[1].cfg.resolution(), Resolution::_8X8);
+
+    // The configs came back the same: storing again gives the same bytes
+    let mut f2 = MemFlash::new(0x8000);
+    r.store(&mut f2, AT).unwrap();
+    assert_eq!(f.mem, f2.mem);
+}
+
+#[test]
+fn fits_max_size() {
+    let f = stored();
+    let used = f.mem[AT as usize..].iter().rposition(|b| *b != 0xff).unwrap() + 1;
+
+    assert!(used <= ConfigRecord::<3>::MAX_SIZE);
+    assert_eq!(f.erases, 1);
+    assert!(f.mem[..AT as usize].iter().all(|b| *b == 0xff));    // nothing written before 'at'
+}
+
+#[test]
+fn stored_over_an_earlier_one() {
+    let mut f = stored();
+    let mut r = record();
+    r.boards[0].calibration = None;
+    r.boards[2].i2c_addr = I2cAddr::from_7bit(0x50);
+
+    r.store(&mut f, AT).unwrap();
+    let back = ConfigRecord::<3>::load(&mut f, AT).unwrap();
+
+    assert!(back.boards[0].calibration.is_none());
+    assert!(back.boards[2].i2c_addr == I2cAddr::from_7bit(0x50));
+}
+
+#[test]
+fn missing_falls_back() {
+    let mut f = MemFlash::new(0x8000);
+
+    assert_eq!(ConfigRecord::<3>::load(&mut f, AT).err(), Some(PersistError::Missing));
+
+    let r = ConfigRecord::<3>::load_or_else(&mut f, AT, defaults);
+    assert!(r.boards[0].i2c_addr == I2cAddr::from_7bit(0x40));
+}
+
+#[test]
+fn corrupted_falls_back() {
+    // Any flipped bit (here: one per 64 bytes) is caught
+    let n = record().boards.iter().map(|b| 16 + b.calibration.as_ref().map_or(0, |_| 1276)).sum::<usize>() + 8 + 4;
+
+    for i in (4..n).step_by(64) {
+        let mut f = stored();
+        f.mem[AT as usize + i] ^= 0x08;
+
+        assert_eq!(ConfigRecord::<3>::load(&mut f, AT).err(), Some(PersistError::Crc), "byte {}", i);
+        let r = ConfigRecord::<3>::load_or_else(&mut f, AT, defaults);
+        assert!(r.boards[0].i2c_addr == I2cAddr::from_7bit(0x40));
+    }
+}
+
+#[test]
+fn record_format() {
+    let f = stored();
+    let at = AT as usize;
+
+    assert_eq!(&f.mem[at..at+6], b"VLCF\x01\x03");
+    assert_eq!(&f.mem[at+8..at+10], &[0x30, 4]);    // first board: address, resolution
+
+    let len = u16::from_le_bytes([f.mem[at+6], f.mem[at+7]]) as usize;
+    assert_eq!(len, 3*16 + 1276);
+    assert_eq!(f.mem[at+8+len..at+8+len+4], crc32(&f.mem[at..at+8+len]).to_le_bytes());
+}
+
+#[test]
+fn newer_version_is_not_read() {
+    let mut f = stored();
+    restamp(&mut f, |h| h[4] = 2);
+
+    assert_eq!(ConfigRecord::<3>::load(&mut f, AT).err(), Some(PersistError::Version{ v: 2 }));
+}
+
+#[test]
+fn other_board_count() {
+    let mut f = stored();
+    assert_eq!(ConfigRecord::<2>::load(&mut f, AT).err(), Some(PersistError::Boards{ n: 3 }));
+
+    restamp(&mut f, |h| h[5] = 4);
+    assert_eq!(ConfigRecord::<3>::load(&mut f, AT).err(), Some(PersistError::Boards{ n: 4 }));
+}
+
+#[test]
+fn invalid_config_is_not_loaded() {
+    // A config that doesn't pass '.try_validate()' (8x8 at 60 Hz) can be stored, but not loaded.
+    let mut r = record();
+    r.boards[1].cfg = RangingConfigDyn::from(RangingConfig::<8>::default().with_mode(Mode::AUTONOMOUS(MsU16(2), HzU8(60))));
+
+    let mut f = MemFlash::new(0x8000);
+    r.store(&mut f, AT).unwrap();
+    assert_eq!(ConfigRecord::<3>::load(&mut f, AT).err(), Some(PersistError::BadRecord));
+}
+
+#[test]
+fn length_beyond_max_size_is_not_read() {
+    // Intact (the CRC is right for the claimed length), but longer than a record can be
+    let mut f = stored();
+    restamp(&mut f, |h| h[6..8].copy_from_slice(&(ConfigRecord::<3>::MAX_SIZE as u16).to_le_bytes()));
+    assert_eq!(ConfigRecord::<3>::load(&mut f, AT).err(), Some(PersistError::BadRecord));
+}
+
+#[cfg(feature = "detection_thresholds")]
+#[test]
+fn thresholds_are_refused() {
+    use vl53l5cx_uld::{Combine, Measurement, ThresholdsConfig, Window};
+
+    let th = ThresholdsConfig::new()
+        .with(0, Measurement::DistanceMm, Window::AtMost(500), Combine::Or).unwrap();
+    let mut r = record();
+    r.boards[2].cfg = RangingConfig::<4>::default().with_thresholds(th).into();
+
+    let mut f = stored();
+    assert_eq!(r.store(&mut f, AT).err(), Some(PersistError::Thresholds{ board: 2 }));
+    assert_eq!(f.erases, 1, "nothing erased");
+    assert!(ConfigRecord::<3>::load(&mut f, AT).is_ok(), "earlier record kept");
+}
+
+#[test]
+fn unaligned_place_is_refused() {
+    let mut f = MemFlash::new(0x8000);
+    assert_eq!(record().store(&mut f, AT + 4).err(), Some(PersistError::Flash));
+}
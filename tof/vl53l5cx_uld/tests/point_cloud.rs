@@ -0,0 +1,65 @@
+/*
+* Host side tests of 'ResultsData::point_cloud()'.
+*
+*   $ cargo test --target x86_64-unknown-linux-gnu --features distance_mm --test point_cloud
+*/
+use vl53l5cx_uld::{ResultsData, TargetStatus};
+
+fn flat<const DIM: usize>(mm: u16) -> ResultsData<DIM> {
+    let mut r = ResultsData::<DIM>::empty();
+    for row in 0..DIM {
+        for col in 0..DIM {
+            r.distance_mm[0][row][col] = mm;
+            r.target_status[0][row][col] = TargetStatus::Valid;
+        }
+    }
+    r
+}
+
+fn close(a: f32, b: f32) -> bool { (a-b).abs() < 0.01 }
+
+#[test]
+fn distance_is_along_the_ray() {
+    let pc = flat::<8>(1000).point_cloud();
+
+    for m in &pc.points[0] {
+        for p in m {
+            assert!(close((p.x*p.x + p.y*p.y + p.z*p.z).sqrt(), 1000.0), "{:?}", p);
+            assert!(p.z > 900.0);
+        }
+    }
+}
+
+#[test]
+fn zone_directions() {
+    let pc = flat::<4>(1000).point_cloud();
+    let p = &pc.points[0];
+
+    // Row 0 is up (-y), column 0 left (-x)
+    assert!(p[0][0].x < 0.0 && p[0][0].y < 0.0);
+    assert!(p[0][3].x > 0.0 && p[0][3].y < 0.0);
+    assert!(p[3][0].x < 0.0 && p[3][0].y > 0.0);
+
+    // Symmetric around the center
+    assert!(close(p[1][1].x, -p[2][2].x) && close(p[1][1].y, -p[2][2].y));
+    assert!(close(p[0][0].z, p[3][3].z));
+}
+
+#[test]
+fn field_of_view() {
+    // Outermost zone centers of an 8x8 are at 19.6875° (of the 22.5° half FoV)
+    let pc = flat::<8>(1000).point_cloud();
+    let p = &pc.points[0][3][7];     // right edge, near the horizon
+
+    assert!(close(p.x / p.z, 19.6875_f32.to_radians().tan()));
+}
+
+#[test]
+fn status_is_carried() {
+    let mut r = flat::<4>(500);
+    r.target_status[0][2][1] = TargetStatus::SigmaTooHigh;
+
+    let pc = r.point_cloud();
+    assert_eq!(pc.points[0][2][1].status, TargetStatus::SigmaTooHigh);
+    assert_eq!(pc.valid_points().count(), 15);
+}
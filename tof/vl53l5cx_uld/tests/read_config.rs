@@ -0,0 +1,119 @@
+/*
+* Host side tests of 'State_HP_Idle::read_config()' and '.verify_config()', against the simulated
+* sensor.
+*
+*   $ cargo test --target x86_64-unknown-linux-gnu --features distance_mm --test read_config
+*/
+mod sim;
+
+use sim::Sim;
+use vl53l5cx_uld::{
+    units::{HzU8, MsU16, PrcU8},
+    Error,
+    Mode,
+    RangingConfig,
+    State_HP_Idle,
+    TargetOrder,
+    VL53L5CX,
+};
+
+// Where the sensor keeps these (vendor 'VL53L5CX_DCI_...'); host order
+const DCI_ZONE_CONFIG: u16 = 0x5450;
+const DCI_TARGET_ORDER: u16 = 0xae64;
+const DCI_SHARPENER: u16 = 0xaed8;
+
+fn init(sim: &Sim) -> State_HP_Idle {
+    VL53L5CX::new_with_ping(sim.platform()).unwrap().init().unwrap()
+}
+
+// Apply 'cfg' (by ranging shortly), and give the sensor back.
+fn applied<const DIM: usize>(sim: &Sim, cfg: &RangingConfig<DIM>) -> State_HP_Idle {
+    init(sim).start_ranging(cfg).unwrap().stop().unwrap()
+}
+
+#[test]
+fn reads_back_what_was_applied() {
+    let sim = Sim::flat(1000);
+    let cfg = RangingConfig::<8>::default()
+        .with_mode(Mode::AUTONOMOUS(MsU16(20), HzU8(10)))
+        .with_sharpener(PrcU8(20))
+        .with_target_order(TargetOrder::CLOSEST);
+
+    let mut vl = applied(&sim, &cfg);
+    let got = vl.read_config::<8>().unwrap();
+
+    assert!(matches!(got.mode(), Mode::AUTONOMOUS(MsU16(20), HzU8(10))));
+    assert_eq!(got.sharpener().map(|PrcU8(v)| v), Some(20));
+    assert!(matches!(got.target_order(), TargetOrder::CLOSEST));
+
+    vl.verify_config(&cfg).unwrap();
+}
+
+#[test]
+fn continuous_and_defaults() {
+    let sim = Sim::flat(1000);
+    let cfg = RangingConfig::<4>::default().with_mode(Mode::CONTINUOUS);
+
+    let mut vl = applied(&sim, &cfg);
+    let got = vl.read_config::<4>().unwrap();
+
+    assert!(matches!(got.mode(), Mode::CONTINUOUS));
+    assert!(got.sharpener().is_none());
+    assert!(matches!(got.target_order(), TargetOrder::STRONGEST));
+
+    vl.verify_config(&cfg).unwrap();
+}
+
+#[test]
+fn sharpener_rounding_is_not_a_mismatch() {
+    // The sensor keeps the sharpener in 1/255 steps; 33% reads back as 32%, 1% as none.
+    for prc in [1, 33, 50, 99] {
+        let sim = Sim::flat(1000);
+        let cfg = RangingConfig::<4>::default().with_sharpener(PrcU8(prc));
+
+        let mut vl = applied(&sim, &cfg);
+        vl.verify_config(&cfg).unwrap();
+    }
+
+    let sim = Sim::flat(1000);
+    let mut vl = applied(&sim, &RangingConfig::<4>::default().with_sharpener(PrcU8(33)));
+    assert_eq!(vl.read_config::<4>().unwrap().sharpener().map(|PrcU8(v)| v), Some(32));
+}
+
+#[test]
+fn other_config_is_a_mismatch() {
+    let sim = Sim::flat(1000);
+    let mut vl = applied(&sim, &RangingConfig::<4>::default());
+
+    let other = RangingConfig::<4>::default().with_mode(Mode::AUTONOMOUS(MsU16(5), HzU8(2)));
+    assert_eq!(vl.verify_config(&other), Err(Error::ConfigMismatch));
+
+    let other = RangingConfig::<4>::default().with_target_order(TargetOrder::CLOSEST);
+    assert_eq!(vl.verify_config(&other), Err(Error::ConfigMismatch));
+}
+
+#[test]
+fn other_resolution_is_a_mismatch() {
+    let sim = Sim::flat(1000);
+    let mut vl = applied(&sim, &RangingConfig::<4>::default());
+
+    assert_eq!(vl.read_config::<8>().err(), Some(Error::ConfigMismatch));
+    assert_eq!(vl.verify_config(&RangingConfig::<8>::default()), Err(Error::ConfigMismatch));
+}
+
+#[test]
+fn changed_behind_our_back() {
+    let sim = Sim::flat(1000);
+    let cfg = RangingConfig::<8>::default().with_sharpener(PrcU8(20));
+    let mut vl = applied(&sim, &cfg);
+
+    // As if (part of) the sensor config was lost
+    sim.set_dci(DCI_SHARPENER + 0xd, &[0]);
+    assert_eq!(vl.verify_config(&cfg), Err(Error::ConfigMismatch));
+
+    sim.set_dci(DCI_TARGET_ORDER, &[0]);     // neither closest (1) nor strongest (2)
+    assert_eq!(vl.read_config::<8>().err(), Some(Error::ConfigMismatch));
+
+    sim.set_dci(DCI_ZONE_CONFIG, &[4, 4]);
+    assert_eq!(vl.read_config::<8>().err(), Some(Error::ConfigMismatch));
+}
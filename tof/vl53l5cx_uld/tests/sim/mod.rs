@@ -0,0 +1,579 @@
+/*
+* Register-level simulation of a VL53L5CX, for host side tests.
+*
+* Implements 'Platform', so the actual ULD C code (and our Rust side) runs against it, end to end:
+*   - page select (0x7fff) and the '(device id, revision id)' read of 'vl53l5cx_ping()'
+*   - boot and MCU stop statuses ("GO2" status at 0x06, 0x07; page 0) and power mode (0x09)
+*   - firmware upload (pages 9..11); only the size is checked
+*   - UI commands (page 2): NVM read, offset / xtalk / default configuration uploads, DCI reads
+*     and writes, start of ranging. The last offset and xtalk uploads are kept, for the tests.
+*   - result frames from a scripted scene, in the format 'vl53l5cx_get_ranging_data()' parses
+*   - motion indicator results, per the aggregate map the plugin has written to DCI
+*   - detection thresholds: kept in DCI as written, for the tests to look at. They don't affect
+*     the frames; the sensor only gates its INT pin by them (and there is no INT here).
+*   - I2C address change ('set_i2c_address()'); the sensor only answers at its current address
+*
+* Not simulated: xtalk calibration; any timing (ranging frequency, integration time). A new frame
+* is ready each time the driver asks, unless frames are held.
+*
+* Faults ('Sim' methods):
+*   - NACK (I2C failure) of the n'th access, from now
+*   - corrupted frame (header and footer id's don't match)
+*   - sensor not taking a new I2C address
+*
+* Power can be cut ('.power_off()'); the sensor then starts anew, as after a power cycle.
+*
+* Note: The ULD swaps each 4-byte group ('VL53L5CX_SwapBuffer()') of what goes through the UI
+*       area. The simulated DCI memory is kept in the host order, i.e. as the C structs see it.
+*
+* Usage (from a test file):
+*   <<
+*       mod sim;
+*       use sim::{Sim, SimTarget, SimZone};
+*   <<
+*/
+#![allow(dead_code)]
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::Rc,
+};
+
+use vl53l5cx_uld::{I2cAddr, Platform, DEFAULT_I2C_ADDR};
+
+const FW_SIZE: usize = 0x8000 + 0x8000 + 0x5000;   // pages 9, 10, 11
+const FW_CHECKSUM: u32 = 0x0c0b_6c9e;               // checked by 'vl53l5cx_init()'
+
+// UI area (page 2)
+const UI_CMD_STATUS: u16 = 0x2c00;
+const UI_CMD_START: u16 = 0x2c04;
+const UI_CMD_END: u16 = 0x2fff;
+
+const UI_NVM_CMD: u16 = 0x2fd8;
+const UI_DEFAULT_CONFIG: u16 = 0x2c34;
+const UI_XTALK: u16 = 0x2cf8;
+const UI_OFFSET: u16 = 0x2e18;
+const UI_DCI_READ: u16 = UI_CMD_END - 11;   // 12 byte command
+const UI_START: u16 = UI_CMD_END - 3;       // 4 byte command; reads give the firmware checksum
+
+// DCI indices
+const DCI_UI_RANGE_CONFIG: u16 = 0x5440;    // '[8..10]': frame size (checked by 'vl53l5cx_start_ranging()')
+const DCI_ZONE_CONFIG: u16 = 0x5450;        // '[0]', '[1]': columns, rows
+const DCI_PIPE_CONTROL: u16 = 0xdb80;       // '[0]': targets per zone
+const DCI_OUTPUT_CONFIG: u16 = 0xd968;      // '{ frame size, number of blocks }'
+const DCI_OUTPUT_ENABLES: u16 = 0xd970;
+const DCI_OUTPUT_LIST: u16 = 0xd980;
+const OUTPUT_LIST_N: usize = 12;
+const DCI_MOTION_DETECTOR_CFG: u16 = 0xbfac;    // 'VL53L5CX_Motion_Configuration'; '.map_id' at [28..92]
+const DCI_DET_THRESH_CONFIG: u16 = 0x5488;      // '[0x11]': thresholds enabled
+const DCI_DET_THRESH_START: u16 = 0xb6e8;       // 64 x 'VL53L5CX_DetectionThresholds' (12 bytes)
+
+// Block indices within a frame
+const METADATA_IDX: u16 = 0x54b4;
+const AMBIENT_RATE_IDX: u16 = 0x54d0;
+const SPAD_COUNT_IDX: u16 = 0x55d0;
+const NB_TARGET_DETECTED_IDX: u16 = 0xdb84;
+const SIGNAL_RATE_IDX: u16 = 0xdbc4;
+const RANGE_SIGMA_MM_IDX: u16 = 0xdec4;
+const DISTANCE_IDX: u16 = 0xdf44;
+const REFLECTANCE_IDX: u16 = 0xe044;
+const TARGET_STATUS_IDX: u16 = 0xe084;
+const MOTION_DETEC_IDX: u16 = 0xd858;
+
+/*
+* One target, as the scene wants it to be seen (i.e. in the units the ULD provides).
+*/
+#[derive(Clone, Debug)]
+pub struct SimTarget {
+    pub distance_mm: u16,
+    pub status: u8,         // 5 = valid
+    pub sigma_mm: u16,
+    pub reflectance: u8,    // %
+    pub signal_kcps: u32,   // per SPAD
+}
+
+impl SimTarget {
+    pub fn at(distance_mm: u16) -> Self {
+        Self{ distance_mm, status: 5, sigma_mm: 2, reflectance: 40, signal_kcps: 100 }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SimZone {
+    pub targets: Vec<SimTarget>,    // closest first; extra ones (beyond 'targets_per_zone_X') are ignored
+    pub ambient_kcps: u32,          // per SPAD
+    pub spads: u32,
+    pub motion: u32,                // motion intensity; an aggregate shows the max of its zones
+}
+
+/*
+* A detection threshold, as the plugin wrote it ('VL53L5CX_DetectionThresholds'). Values are in the
+* sensor's units (e.g. mm*4); 'zone_num' has the "last" marker (0x80) on the last entry.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimThreshold {
+    pub low: i32,
+    pub high: i32,
+    pub measurement: u8,
+    pub type_: u8,
+    pub zone_num: u8,
+    pub op: u8,
+}
+
+/*
+* Scene: '(frame number, zone, zones) -> SimZone'. Zones are in the ULD order (row by row).
+*/
+pub type Scene = Box<dyn FnMut(u32, usize, usize) -> SimZone>;
+
+struct State {
+    page: u8,
+    dev_addr: u8,       // 7-bit; what the sensor answers to
+    bus_addr: u8,       // 7-bit; where the 'Platform' is talking to
+    regs: HashMap<(u8,u16),u8>,     // plain registers, pages 0 and 1
+    fw_bytes: usize,
+    dci: Vec<u8>,
+    response: Vec<u8>,  // readable at 'UI_CMD_START'
+    nvm: Vec<u8>,       // given for the NVM read command
+    offset_upload: Vec<u8>,     // last written, as is
+    xtalk_upload: Vec<u8>,
+
+    ranging: bool,
+    streamcount: u8,
+    frame_no: u32,
+    scene: Scene,
+    temp_degc: i8,
+
+    // faults
+    nack_in: Option<usize>,
+    corrupt_reads: u32,
+    ignore_addr_change: bool,
+    hold_frames: bool,
+
+    // statistics
+    accesses: usize,
+    frames_read: u32,
+}
+
+/*
+* Handle that the test keeps; the 'SimPl' goes to the driver.
+*/
+#[derive(Clone)]
+pub struct Sim(Rc<RefCell<State>>);
+
+pub struct SimPl(Rc<RefCell<State>>);
+
+impl Sim {
+    pub fn new(scene: Scene) -> Self {
+        let st = State{
+            page: 0,
+            dev_addr: DEFAULT_I2C_ADDR.as_7bit(),
+            bus_addr: DEFAULT_I2C_ADDR.as_7bit(),
+            regs: HashMap::new(),
+            fw_bytes: 0,
+            dci: vec![0; 0x1_0000],
+            response: Vec::new(),
+            nvm: vec![0; 492],      // 'VL53L5CX_NVM_DATA_SIZE'
+            offset_upload: Vec::new(),
+            xtalk_upload: Vec::new(),
+            ranging: false,
+            streamcount: 0,
+            frame_no: 0,
+            scene,
+            temp_degc: 25,
+            nack_in: None,
+            corrupt_reads: 0,
+            ignore_addr_change: false,
+            hold_frames: false,
+            accesses: 0,
+            frames_read: 0,
+        };
+        Self(Rc::new(RefCell::new(st)))
+    }
+
+    /*
+    * Every zone sees a single target at 'mm'.
+    */
+    pub fn flat(mm: u16) -> Self {
+        Self::new(Box::new(move |_,_,_| SimZone{ targets: vec![SimTarget::at(mm)], ambient_kcps: 1, spads: 100, motion: 0 }))
+    }
+
+    pub fn platform(&self) -> SimPl {
+        SimPl(self.0.clone())
+    }
+
+    // Faults
+    //
+    pub fn nack_after(&self, n: usize) { self.0.borrow_mut().nack_in = Some(n); }
+    pub fn corrupt_next_frame(&self) { self.corrupt_next_reads(1); }
+    pub fn corrupt_next_reads(&self, n: u32) { self.0.borrow_mut().corrupt_reads = n; }     // also of the same frame
+    pub fn ignore_addr_change(&self) { self.0.borrow_mut().ignore_addr_change = true; }
+    pub fn hold_frames(&self, on: bool) { self.0.borrow_mut().hold_frames = on; }
+
+    /*
+    * The sensor makes 'n' frames, without the driver asking. Unless read, they are lost.
+    */
+    pub fn produce_frames(&self, n: u32) {
+        let mut st = self.0.borrow_mut();
+        st.streamcount = ((st.streamcount as u32 + n) % 255) as u8;
+        st.frame_no += n;
+    }
+
+    pub fn set_temp(&self, degc: i8) { self.0.borrow_mut().temp_degc = degc; }
+
+    /*
+    * As if the power was cut: back to the default I2C address, no firmware, not ranging; registers
+    * and DCI cleared. The scene, NVM contents and fault settings stay.
+    */
+    pub fn power_off(&self) {
+        let mut st = self.0.borrow_mut();
+        st.page = 0;
+        st.dev_addr = DEFAULT_I2C_ADDR.as_7bit();
+        st.regs.clear();
+        st.fw_bytes = 0;
+        st.dci.fill(0);
+        st.response.clear();
+        st.offset_upload.clear();
+        st.xtalk_upload.clear();
+        st.ranging = false;
+        st.streamcount = 0;
+    }
+
+    /*
+    * The NVM contents (492 bytes); the offset calibration of the sensor is at the start.
+    */
+    pub fn set_nvm(&self, bs: &[u8]) {
+        assert_eq!(bs.len(), 492);
+        self.0.borrow_mut().nvm = bs.to_vec();
+    }
+
+    /*
+    * Change the sensor's DCI contents behind the driver's back; e.g. as if a brown-out had reset
+    * some of its configuration. 'bs' in host order.
+    */
+    pub fn set_dci(&self, index: u16, bs: &[u8]) { self.0.borrow_mut().dci_put(index, bs); }
+
+    // Observations
+    //
+    pub fn dev_addr(&self) -> u8 { self.0.borrow().dev_addr }
+    pub fn fw_uploaded(&self) -> bool { self.0.borrow().fw_bytes == FW_SIZE }
+    pub fn is_ranging(&self) -> bool { self.0.borrow().ranging }
+    pub fn is_asleep(&self) -> bool { self.0.borrow().reg(0, 0x09) == 0x02 }
+    pub fn frames_read(&self) -> u32 { self.0.borrow().frames_read }
+    pub fn accesses(&self) -> usize { self.0.borrow().accesses }
+    pub fn offset_upload(&self) -> Vec<u8> { self.0.borrow().offset_upload.clone() }
+    pub fn xtalk_upload(&self) -> Vec<u8> { self.0.borrow().xtalk_upload.clone() }
+
+    pub fn dci(&self, index: u16, n: usize) -> Vec<u8> {
+        let i = index as usize;
+        self.0.borrow().dci[i..i+n].to_vec()
+    }
+
+    pub fn thresholds_enabled(&self) -> bool { self.dci(DCI_DET_THRESH_CONFIG + 0x11, 1)[0] != 0 }
+
+    /*
+    * The thresholds, up to the one marked last. Empty if none were written.
+    */
+    pub fn thresholds(&self) -> Vec<SimThreshold> {
+        let bs = self.dci(DCI_DET_THRESH_START, 64*12);
+        let mut v = Vec::new();
+
+        for c in bs.chunks(12) {
+            let t = SimThreshold{
+                low: i32::from_le_bytes(c[0..4].try_into().unwrap()),
+                high: i32::from_le_bytes(c[4..8].try_into().unwrap()),
+                measurement: c[8],
+                type_: c[9],
+                zone_num: c[10],
+                op: c[11],
+            };
+            let last = t.zone_num & 0x80 != 0;
+            v.push(t);
+            if last { return v }
+        }
+        Vec::new()
+    }
+}
+
+impl Platform for SimPl {
+    fn rd_bytes(&mut self, index: u16, buf: &mut [u8]) -> Result<(),()> {
+        let mut st = self.0.borrow_mut();
+        st.access()?;
+        st.read(index, buf);
+        Ok(())
+    }
+
+    fn wr_bytes(&mut self, index: u16, vs: &[u8]) -> Result<(),()> {
+        let mut st = self.0.borrow_mut();
+        st.access()?;
+        st.write(index, vs);
+        Ok(())
+    }
+
+    fn delay_ms(&mut self, _ms: u32) {}
+
+    fn addr_changed(&mut self, addr: &I2cAddr) {
+        self.0.borrow_mut().bus_addr = addr.as_7bit();
+    }
+}
+
+impl State {
+    fn access(&mut self) -> Result<(),()> {
+        self.accesses += 1;
+
+        if self.bus_addr != self.dev_addr { return Err(()) }   // nobody there
+
+        match self.nack_in {
+            Some(0) => { self.nack_in = None; Err(()) },
+            Some(n) => { self.nack_in = Some(n-1); Ok(()) },
+            None => Ok(())
+        }
+    }
+
+    fn reg(&self, page: u8, index: u16) -> u8 {
+        *self.regs.get(&(page,index)).unwrap_or(&0)
+    }
+
+    fn mcu_stopped(&self) -> bool {
+        self.reg(0, 0x14) == 0x01
+    }
+
+    fn read(&mut self, index: u16, buf: &mut [u8]) {
+        buf.fill(0);
+
+        match (self.page, index) {
+            (_, 0x7fff) => buf[0] = self.page,
+            (0, 0x0000) => {
+                buf[0] = 0xf0;
+                if buf.len() > 1 { buf[1] = 0x02 };
+            },
+            (0, 0x0006) => {    // GO2 status 0
+                buf[0] = if self.mcu_stopped() { 0x81 }
+                    else if self.reg(0, 0x09) == 0x02 { 0x00 }  // asleep
+                    else { 0x01 };
+            },
+            (0, 0x0007) => {    // GO2 status 1
+                buf[0] = if self.mcu_stopped() { 0x84 } else { 0x00 };
+            },
+            (1, 0x0021) => buf[0] = 0x10,   // firmware access granted
+            (0|1, i) => buf[0] = self.reg(self.page, i),
+
+            (2, 0x0000) if buf.len() == 4 => self.read_ready(buf),
+            (2, 0x0000) => self.read_frame(buf),
+            (2, UI_CMD_STATUS) => {
+                // '[0]' == 2: NVM data available; '[1]' == 3: command done
+                let n = buf.len().min(4);
+                buf[..n].copy_from_slice(&[0x02, 0x03, 0x00, 0x00][..n]);
+            },
+            (2, UI_START) => buf[..4].copy_from_slice(&FW_CHECKSUM.to_be_bytes()),
+            (2, i) if i >= UI_CMD_START => {
+                let o = (i - UI_CMD_START) as usize;
+                for (j,b) in buf.iter_mut().enumerate() {
+                    *b = *self.response.get(o+j).unwrap_or(&0);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    fn write(&mut self, index: u16, vs: &[u8]) {
+        match (self.page, index) {
+            (_, 0x7fff) => self.page = vs[0],
+            (0, 0x0004) if !self.ignore_addr_change => self.dev_addr = vs[0],
+            (0, 0x0004) => {},
+            (0, 0x0014) if vs[0] == 0x01 => {     // MCU stop ('vl53l5cx_stop_ranging()')
+                self.ranging = false;
+                self.regs.insert((0, 0x14), 0x01);
+            },
+            (0|1, i) => { self.regs.insert((self.page, i), vs[0]); },
+            (9..=11, _) => self.fw_bytes += vs.len(),
+
+            (2, UI_NVM_CMD) => self.response = self.nvm.clone(),
+            (2, UI_DEFAULT_CONFIG) => {
+                // The real sensor is told many things; we only need the zone config.
+                self.dci_put(DCI_ZONE_CONFIG, &[4,4,0,0,8,8,0,0]);
+            },
+            (2, UI_OFFSET) => self.offset_upload = vs.to_vec(),
+            (2, UI_XTALK) => self.xtalk_upload = vs.to_vec(),
+            (2, UI_DCI_READ) if vs.len() == 12 => self.dci_read_cmd(vs),
+            (2, UI_START) if vs.len() == 4 => self.start_ranging(),
+            (2, i) if i as usize + vs.len() == UI_CMD_END as usize + 1 => self.dci_write_cmd(vs),
+            _ => {}
+        }
+    }
+
+    fn dci_put(&mut self, index: u16, vs: &[u8]) {
+        let i = index as usize;
+        self.dci[i..i+vs.len()].copy_from_slice(vs);
+    }
+
+    fn dci_u32(&self, index: u16) -> u32 {
+        let i = index as usize;
+        u32::from_le_bytes(self.dci[i..i+4].try_into().unwrap())
+    }
+
+    /*
+    * '{ index_hi, index_lo, size_hi, size_lo<<4 }' + data (sensor order) + 8 bytes footer
+    */
+    fn dci_write_cmd(&mut self, vs: &[u8]) {
+        let index = u16::from_be_bytes([vs[0], vs[1]]);
+        let n = (vs[2] as usize) << 4 | (vs[3] as usize) >> 4;
+        assert_eq!(vs.len(), n+12, "DCI write: unexpected size");
+
+        let data = swapped(&vs[4..4+n]);
+        self.dci_put(index, &data);
+
+        if index == DCI_OUTPUT_CONFIG {
+            // The firmware tells this back, as part of the range config.
+            let frame_size = self.dci_u32(DCI_OUTPUT_CONFIG) as u16;
+            self.dci_put(DCI_UI_RANGE_CONFIG + 8, &frame_size.to_le_bytes());
+        }
+    }
+
+    /*
+    * Response (at 'UI_CMD_START') is 4 bytes header + data + 8 bytes footer, in the sensor order.
+    */
+    fn dci_read_cmd(&mut self, vs: &[u8]) {
+        let index = u16::from_be_bytes([vs[0], vs[1]]) as usize;
+        let n = (vs[2] as usize) << 4 | (vs[3] as usize) >> 4;
+
+        let mut r = vec![0_u8; 4];
+        r.extend_from_slice(&self.dci[index..index+n]);
+        r.resize(4+n+8, 0);
+        self.response = swapped(&r);
+    }
+
+    fn start_ranging(&mut self) {
+        self.ranging = true;
+    }
+
+    fn zones(&self) -> usize {
+        let i = DCI_ZONE_CONFIG as usize;
+        (self.dci[i] as usize) * (self.dci[i+1] as usize)
+    }
+
+    /*
+    * 'vl53l5cx_check_data_ready()': '[streamcount, 0x05, 0x05, 0x10]' tells of a new frame
+    * (streamcount changed). Each call brings a new frame, unless held.
+    */
+    fn read_ready(&mut self, buf: &mut [u8]) {
+        if !self.ranging { return }
+
+        if !self.hold_frames {
+            self.streamcount = (self.streamcount + 1) % 255;    // 255 is never used
+            self.frame_no += 1;
+        }
+        buf.copy_from_slice(&[self.streamcount, 0x05, 0x05, 0x10]);
+    }
+
+    /*
+    * Compose a frame, as given by the output list, enables and config the ULD has written to DCI.
+    *
+    *   [0..4]      streamcount etc. (as in 'read_ready()')
+    *   [8..10]     header id (big endian, after swapping)
+    *   [12..16]    start block
+    *   [16..]      blocks: header (type:4, size:12, idx:16) + payload
+    *   [-12..]     footer; id at [-4..-2]
+    */
+    fn read_frame(&mut self, buf: &mut [u8]) {
+        let size = self.dci_u32(DCI_OUTPUT_CONFIG) as usize;
+        assert_eq!(buf.len(), size, "Frame read of unexpected size");
+
+        let zones = self.zones();
+        let targets = self.dci[DCI_PIPE_CONTROL as usize] as usize;     // 'VL53L5CX_NB_TARGET_PER_ZONE'
+        let temp = self.temp_degc;
+        let frame_no = self.frame_no;
+        let sz: Vec<SimZone> = (0..zones).map(|z| (self.scene)(frame_no, z, zones)).collect();
+
+        let mut f = vec![0_u8; size];
+        let id = (frame_no as u16).to_be_bytes();
+        f[8..10].copy_from_slice(&id);
+
+        let enables = [self.dci_u32(DCI_OUTPUT_ENABLES), self.dci_u32(DCI_OUTPUT_ENABLES+4)];
+        let mut o = 16;
+
+        for i in 1..OUTPUT_LIST_N {     // 0: start block; in the header
+            if enables[i/32] & (1 << (i%32)) == 0 { continue }
+
+            let bh = self.dci_u32(DCI_OUTPUT_LIST + 4*i as u16);
+            let (typ, n, idx) = ((bh & 0xf) as usize, ((bh >> 4) & 0xfff) as usize, (bh >> 16) as u16);
+            let msize = if (1..0xd).contains(&typ) { typ*n } else { n };
+
+            f[o..o+4].copy_from_slice(&bh.to_le_bytes());
+            o += 4;
+            let p = &mut f[o..o+msize];
+
+            let tgt = |z: usize, t: usize| sz[z].targets.get(t);
+
+            match idx {
+                METADATA_IDX => p[8] = temp as u8,
+                AMBIENT_RATE_IDX => put_zones(p, zones, |z| (sz[z].ambient_kcps * 2048).to_le_bytes()),
+                SPAD_COUNT_IDX => put_zones(p, zones, |z| sz[z].spads.to_le_bytes()),
+                NB_TARGET_DETECTED_IDX => put_zones(p, zones, |z| [sz[z].targets.len().min(targets) as u8]),
+                SIGNAL_RATE_IDX => put_targets(p, zones, targets, |z,t| tgt(z,t).map_or(0, |x| x.signal_kcps * 2048).to_le_bytes()),
+                RANGE_SIGMA_MM_IDX => put_targets(p, zones, targets, |z,t| tgt(z,t).map_or(0, |x| x.sigma_mm * 128).to_le_bytes()),
+                DISTANCE_IDX => put_targets(p, zones, targets, |z,t| tgt(z,t).map_or(0, |x| (x.distance_mm as i16) * 4).to_le_bytes()),
+                REFLECTANCE_IDX => put_targets(p, zones, targets, |z,t| [tgt(z,t).map_or(0, |x| x.reflectance * 2)]),
+                TARGET_STATUS_IDX => put_targets(p, zones, targets, |z,t| [tgt(z,t).map_or(0, |x| x.status)]),
+                MOTION_DETEC_IDX => self.put_motion(p, &sz),
+                _ => {}     // common data: zeros
+            }
+            o += msize;
+        }
+        assert_eq!(o + 12, size, "Frame layout doesn't match the size the ULD expects");
+
+        let footer_id = if self.corrupt_reads > 0 { self.corrupt_reads -= 1; [!id[0], !id[1]] } else { id };
+        f[size-4..size-2].copy_from_slice(&footer_id);
+
+        let mut f = swapped(&f);
+        f[..4].copy_from_slice(&[self.streamcount, 0x05, 0x05, 0x10]);
+
+        buf.copy_from_slice(&f);
+        self.frames_read += 1;
+    }
+
+    /*
+    * 'VL53L5CX_MotionIndicator': '{ global_indicator_1, _2: u32, status, nb_of_detected_aggregates,
+    * nb_of_aggregates, spare: u8, motion: [u32;32] }'. Zones map to the aggregates by the
+    * '.map_id' the plugin wrote ('vl53l5cx_motion_indicator_set_resolution()').
+    */
+    fn put_motion(&self, p: &mut [u8], sz: &[SimZone]) {
+        let i = DCI_MOTION_DETECTOR_CFG as usize + 28;
+        let map = &self.dci[i..i+64];
+
+        let mut aggr = [0_u32; 32];
+        for (z, x) in sz.iter().enumerate() {
+            let a = map[z] as i8;     // negative: zone not in use
+            if a >= 0 {
+                aggr[a as usize] = aggr[a as usize].max(x.motion);
+            }
+        }
+
+        p[9] = aggr.iter().filter(|&&v| v > 0).count() as u8;
+        p[10] = self.dci[DCI_MOTION_DETECTOR_CFG as usize + 20];    // '.nb_of_aggregates'
+        for (j, v) in aggr.iter().enumerate() {
+            p[12+4*j..16+4*j].copy_from_slice(&v.to_le_bytes());
+        }
+    }
+}
+
+fn put_zones<const N: usize>(p: &mut [u8], zones: usize, f: impl Fn(usize) -> [u8; N]) {
+    for z in 0..zones {
+        p[z*N..(z+1)*N].copy_from_slice(&f(z));
+    }
+}
+
+fn put_targets<const N: usize>(p: &mut [u8], zones: usize, targets: usize, f: impl Fn(usize,usize) -> [u8; N]) {
+    for z in 0..zones {
+        for t in 0..targets {
+            let i = z*targets + t;
+            p[i*N..(i+1)*N].copy_from_slice(&f(z,t));
+        }
+    }
+}
+
+// Each 4-byte group 'ABCD' -> 'DCBA' (like 'VL53L5CX_SwapBuffer()').
+fn swapped(bs: &[u8]) -> Vec<u8> {
+    assert!(bs.len() % 4 == 0, "Not 4-byte groups: {}", bs.len());
+    bs.chunks(4).flat_map(|c| [c[3], c[2], c[1], c[0]]).collect()
+}
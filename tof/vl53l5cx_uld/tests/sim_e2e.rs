@@ -0,0 +1,202 @@
+/*
+* Host side, end to end tests of the driver (Rust + ULD C code), against a simulated sensor.
+*
+*   $ cargo test --target x86_64-unknown-linux-gnu --features distance_mm --test sim_e2e
+*/
+mod sim;
+
+use sim::{Sim, SimTarget, SimZone};
+use vl53l5cx_uld::{
+    units::TempC,
+    Confidence,
+    Error,
+    I2cAddr,
+    RangingConfig,
+    State_HP_Idle,
+    TargetStatus,
+    VL53L5CX,
+};
+
+fn init(sim: &Sim) -> State_HP_Idle {
+    VL53L5CX::new_with_ping(sim.platform()).unwrap()
+        .init().unwrap()
+}
+
+#[test]
+fn init_uploads_firmware() {
+    let sim = Sim::flat(1000);
+    let _vl = init(&sim);
+
+    assert!(sim.fw_uploaded());
+}
+
+#[test]
+fn ranging_4x4() {
+    // Distance grows by zone and by frame
+    let sim = Sim::new(Box::new(|frame, z, _| SimZone{
+        targets: vec![SimTarget::at(100 + 10*z as u16 + frame as u16)],
+        ..SimZone::default()
+    }));
+    sim.set_temp(31);
+
+    let vl = init(&sim);
+    let mut r = vl.start_ranging(&RangingConfig::<4>::default()).unwrap();
+    assert!(sim.is_ranging());
+
+    for frame in 1..=3 {
+        assert!(r.is_ready().unwrap());
+        let (res, TempC(t)) = r.get_data().unwrap();
+
+        assert_eq!(t, 31);
+        assert_eq!(res.distance_mm[0][0][0], 100 + frame);
+        assert_eq!(res.distance_mm[0][1][3], 100 + 10*7 + frame);     // zone 7
+    }
+    assert_eq!(sim.frames_read(), 3);
+
+    let _vl = r.stop().unwrap();
+    assert!(!sim.is_ranging());
+}
+
+#[test]
+fn ranging_8x8() {
+    let sim = Sim::new(Box::new(|_, z, zones| SimZone{
+        targets: vec![SimTarget::at(if z == zones-1 { 2000 } else { 500 })],
+        ..SimZone::default()
+    }));
+
+    let vl = init(&sim);
+    let mut r = vl.start_ranging(&RangingConfig::<8>::default()).unwrap();
+
+    assert!(r.is_ready().unwrap());
+    let (res, _) = r.get_data().unwrap();
+
+    assert_eq!(res.distance_mm[0][0][0], 500);
+    assert_eq!(res.distance_mm[0][7][7], 2000);
+}
+
+#[test]
+fn target_status_mapping() {
+    // Zone 0: 100% valid; 1: 50%; 2: error; 3: not in UM2884 (must not panic)
+    let sim = Sim::new(Box::new(|_, z, _| SimZone{
+        targets: vec![SimTarget{ status: [5, 9, 13, 200][z.min(3)], ..SimTarget::at(1000) }],
+        ..SimZone::default()
+    }));
+    let vl = init(&sim);
+    let mut r = vl.start_ranging(&RangingConfig::<4>::default()).unwrap();
+
+    assert!(r.is_ready().unwrap());
+    let (res, _) = r.get_data().unwrap();
+    let st = &res.target_status[0][0];
+
+    assert_eq!(st[..4], [TargetStatus::Valid, TargetStatus::ValidLargePulse, TargetStatus::InconsistentData, TargetStatus::Unknown(200)]);
+    assert!(st[0].is_valid() && !st[1].is_valid());
+    assert_eq!(st[1].confidence(), Confidence::Half);
+    assert_eq!(st[2].confidence(), Confidence::Low);
+    assert_eq!(st[3].confidence(), Confidence::NoRange);
+    assert_eq!(st[3].as_u8(), 200);
+}
+
+#[test]
+fn not_ready_without_new_frame() {
+    let sim = Sim::flat(1000);
+    let vl = init(&sim);
+    let mut r = vl.start_ranging(&RangingConfig::<4>::default()).unwrap();
+
+    assert!(r.is_ready().unwrap());
+    r.get_data().unwrap();
+
+    sim.hold_frames(true);
+    assert!(!r.is_ready().unwrap());
+
+    sim.hold_frames(false);
+    assert!(r.is_ready().unwrap());
+}
+
+#[test]
+fn sleep_and_wake_up() {
+    let sim = Sim::flat(1000);
+    let vl = init(&sim);
+
+    let lp = vl.go_to_sleep().unwrap();
+    assert!(sim.is_asleep());
+
+    let _vl = lp.wake_up().unwrap();
+    assert!(!sim.is_asleep());
+}
+
+#[test]
+fn i2c_address_change() {
+    let sim = Sim::flat(1000);
+    let mut vl = init(&sim);
+
+    vl.set_i2c_address(&I2cAddr::from_7bit(0x30)).unwrap();
+    assert_eq!(sim.dev_addr(), 0x30);
+
+    // Continues at the new address
+    let mut r = vl.start_ranging(&RangingConfig::<4>::default()).unwrap();
+    assert!(r.is_ready().unwrap());
+    r.get_data().unwrap();
+}
+
+#[test]
+fn i2c_address_change_not_taken() {
+    let sim = Sim::flat(1000);
+    let mut vl = init(&sim);
+
+    sim.ignore_addr_change();
+    assert_eq!(vl.set_i2c_address(&I2cAddr::from_7bit(0x30)), Err(Error::AddrChangeNotConfirmed));
+}
+
+#[test]
+fn nack_during_init() {
+    let sim = Sim::flat(1000);
+    let vl = VL53L5CX::new_with_ping(sim.platform()).unwrap();
+
+    sim.nack_after(20);
+    match vl.init() {
+        Err(e) => assert_eq!(e, Error::Generic),
+        Ok(_) => panic!("init should fail")
+    }
+}
+
+#[test]
+fn nack_during_get_data() {
+    let sim = Sim::flat(1000);
+    let vl = init(&sim);
+    let mut r = vl.start_ranging(&RangingConfig::<4>::default()).unwrap();
+
+    assert!(r.is_ready().unwrap());
+    sim.nack_after(0);
+    assert_eq!(r.get_data().err(), Some(Error::Generic));
+
+    // Next frame is fine
+    assert!(r.is_ready().unwrap());
+    assert!(r.get_data().is_ok());
+}
+
+#[test]
+fn corrupted_frame() {
+    let sim = Sim::flat(1000);
+    let vl = init(&sim);
+    let mut r = vl.start_ranging(&RangingConfig::<4>::default()).unwrap();
+
+    sim.corrupt_next_frame();
+    assert!(r.is_ready().unwrap());
+    assert_eq!(r.get_data().err(), Some(Error::CorruptedFrame));
+
+    assert!(r.is_ready().unwrap());
+    assert!(r.get_data().is_ok());
+}
+
+#[test]
+fn abandon_does_not_touch_the_sensor() {
+    let sim = Sim::flat(1000);
+    let vl = init(&sim);
+    let r = vl.start_ranging(&RangingConfig::<4>::default()).unwrap();
+
+    let n = sim.accesses();
+    r.abandon();
+
+    assert_eq!(sim.accesses(), n);
+    assert!(sim.is_ranging());
+}
@@ -0,0 +1,121 @@
+/*
+* Host side tests of 'RecordingPlatform' and 'ReplayPlatform'. A session is recorded against the
+* simulated sensor, then played back.
+*
+*   $ cargo test --target x86_64-unknown-linux-gnu --features trace,distance_mm --test trace
+*/
+mod sim;
+
+use std::{cell::RefCell, rc::Rc};
+
+use sim::Sim;
+use vl53l5cx_uld::{
+    units::{MsU16, HzU8},
+    Divergence,
+    Mode,
+    Platform,
+    RangingConfig,
+    RecordingPlatform,
+    ReplayPlatform,
+    TraceOp,
+    VL53L5CX,
+    TRACE_MAGIC,
+};
+
+/*
+* The session: init, one frame of 4x4 ranging, stop.
+*/
+fn session<P: Platform + 'static>(p: P, cfg: &RangingConfig<4>) -> Result<u16, vl53l5cx_uld::Error> {
+    let vl = VL53L5CX::new_with_ping(p)?.init()?;
+    let mut r = vl.start_ranging(cfg)?;
+
+    assert!(r.is_ready()?);
+    let (res, _) = r.get_data()?;
+    r.stop()?;
+
+    Ok(res.distance_mm[0][0][0])
+}
+
+fn record(sim: &Sim) -> &'static [u8] {
+    let buf = Rc::new(RefCell::new(Vec::<u8>::new()));
+    let b2 = buf.clone();
+
+    let pl = RecordingPlatform::new(sim.platform(), move |bs: &[u8]| b2.borrow_mut().extend_from_slice(bs));
+    assert_eq!(session(pl, &RangingConfig::default()).unwrap(), 1234);
+
+    let v = buf.borrow().clone();
+    v.leak()
+}
+
+type Seen = Rc<RefCell<Option<Divergence>>>;
+
+fn replay(trace: &'static [u8]) -> (ReplayPlatform<impl FnMut(Divergence)>, Seen) {
+    let d = Rc::new(RefCell::new(None));
+    let d2 = d.clone();
+
+    (ReplayPlatform::new(trace, move |x| { *d2.borrow_mut() = Some(x); }), d)
+}
+
+#[test]
+fn trace_is_compact() {
+    let trace = record(&Sim::flat(1234));
+
+    assert_eq!(&trace[..4], TRACE_MAGIC);
+    assert!(trace.len() < 20_000, "trace unexpectedly long: {}", trace.len());   // the firmware upload (84kB) is hashed
+}
+
+#[test]
+fn replay_matches() {
+    let trace = record(&Sim::flat(1234));
+    let (pl, d) = replay(trace);
+
+    assert_eq!(session(pl, &RangingConfig::default()).unwrap(), 1234);
+    assert_eq!(*d.borrow(), None);
+}
+
+#[test]
+fn replay_flags_changed_config() {
+    let trace = record(&Sim::flat(1234));
+    let (pl, d) = replay(trace);
+
+    let cfg = RangingConfig::<4>::default().with_mode(Mode::AUTONOMOUS(MsU16(10), HzU8(2)));
+    assert!(session(pl, &cfg).is_err());
+
+    let div = d.borrow();
+    match *div {
+        Some(Divergence::DataMismatch{ op: TraceOp::Wr{..}, .. }) => {},
+        ref x => panic!("unexpected: {:?}", x)
+    }
+}
+
+#[test]
+fn replay_flags_missing_calls() {
+    let trace = record(&Sim::flat(1234));
+    let (pl, d) = replay(trace);
+
+    // Just init; the rest of the trace is left
+    drop( VL53L5CX::new_with_ping(pl).unwrap().init().unwrap() );
+
+    let div = d.borrow();
+    match *div {
+        Some(Divergence::NotConsumed{..}) => {},
+        ref x => panic!("unexpected: {:?}", x)
+    }
+}
+
+#[test]
+fn recorded_failure_is_replayed() {
+    let sim = Sim::flat(1234);
+    let buf = Rc::new(RefCell::new(Vec::<u8>::new()));
+    let b2 = buf.clone();
+
+    sim.nack_after(1);      // the id read of the ping
+    let pl = RecordingPlatform::new(sim.platform(), move |bs: &[u8]| b2.borrow_mut().extend_from_slice(bs));
+    assert!(VL53L5CX::new_with_ping(pl).is_err());
+
+    let trace: &'static [u8] = buf.borrow().clone().leak();
+    let (pl, d) = replay(trace);
+
+    assert!(VL53L5CX::new_with_ping(pl).is_err());
+    assert_eq!(*d.borrow(), None);
+}
@@ -0,0 +1,140 @@
+/*
+* Host side tests of the xtalk blob handling: the 'XtalkData' wrapper, and reading / setting the
+* data against the simulated sensor. The calibration itself is not simulated; only its argument
+* checks are tested.
+*
+*   $ cargo test --target x86_64-unknown-linux-gnu --features xtalk,distance_mm --test xtalk_data
+*/
+mod sim;
+
+use sim::Sim;
+use vl53l5cx_uld::{
+    units::PrcU8,
+    ConfigError,
+    Error,
+    RangingConfig,
+    State_HP_Idle,
+    XtalkData,
+    VL53L5CX,
+    XTALK_SIZE,
+};
+
+const XTALK_AT: usize = 8 + 488;    // in a 'CalibrationBlob'
+const FOOTER: usize = 8;            // last bytes of the xtalk data; written by the ULD
+
+fn blob() -> [u8; XTALK_SIZE] {
+    core::array::from_fn(|i| (i*7 % 251) as u8)
+}
+
+fn init(sim: &Sim) -> State_HP_Idle {
+    VL53L5CX::new_with_ping(sim.platform()).unwrap().init().unwrap()
+}
+
+// The xtalk data in use, as seen through 'CalibrationBlob' (the ULD's working memory)
+fn in_use(vl: &State_HP_Idle) -> [u8; XTALK_SIZE] {
+    vl.calibration().as_bytes()[XTALK_AT..XTALK_AT+XTALK_SIZE].try_into().unwrap()
+}
+
+// The vendor default, with some bytes changed; as if calibrated
+fn calibrated(vl: &State_HP_Idle) -> XtalkData {
+    let mut bs = in_use(vl);
+    for b in &mut bs[..100] { *b ^= 0x5a; }
+    XtalkData::from_bytes(&bs)
+}
+
+// Start an 8x8 ranging (which sends the xtalk data), and see what was sent
+fn uploaded(sim: &Sim, vl: State_HP_Idle) -> Vec<u8> {
+    let r = vl.start_ranging(&RangingConfig::<8>::default()).unwrap();
+    r.stop().unwrap();
+    sim.xtalk_upload()
+}
+
+#[test]
+fn size_is_vendor_buffer() {
+    assert_eq!(XTALK_SIZE, 776);
+}
+
+#[test]
+fn bytes_roundtrip() {
+    let bs = blob();
+    let x = XtalkData::from_bytes(&bs);
+    assert_eq!(x.as_bytes(), &bs);
+}
+
+#[test]
+fn from_slice_roundtrip() {
+    let bs = blob();
+    let x = XtalkData::try_from(&bs[..]).unwrap();
+    assert_eq!(x.as_bytes(), &bs);
+}
+
+#[test]
+fn from_slice_wrong_length() {
+    let bs = blob();
+    assert!(XtalkData::try_from(&bs[..XTALK_SIZE-1]).is_err());
+
+    let mut v = bs.to_vec();
+    v.push(0);
+    assert!(XtalkData::try_from(&v[..]).is_err());
+    assert!(XtalkData::try_from(&[][..]).is_err());
+}
+
+#[test]
+fn default_data_is_read() {
+    let sim = Sim::flat(1000);
+    let mut vl = init(&sim);
+
+    let x = vl.xtalk_data().unwrap();
+    assert_eq!(x.as_bytes()[..XTALK_SIZE-FOOTER], in_use(&vl)[..XTALK_SIZE-FOOTER]);
+}
+
+#[test]
+fn set_data_is_read_back() {
+    let sim = Sim::flat(1000);
+    let mut vl = init(&sim);
+    let x = calibrated(&vl);
+
+    vl.set_xtalk_data(&x).unwrap();
+    assert_eq!(&in_use(&vl), x.as_bytes());
+
+    let back = vl.xtalk_data().unwrap();
+    assert_eq!(back.as_bytes()[..XTALK_SIZE-FOOTER], x.as_bytes()[..XTALK_SIZE-FOOTER]);
+}
+
+#[test]
+fn set_data_reaches_the_sensor() {
+    let sim = Sim::flat(1000);
+    let default = uploaded(&sim, init(&sim));
+
+    let mut vl = init(&sim);
+    let x = calibrated(&vl);
+    vl.set_xtalk_data(&x).unwrap();
+
+    let sent = uploaded(&sim, vl);
+    assert_ne!(sent, default);
+
+    // Another sensor, given the same data, gets the same
+    let b = Sim::flat(1000);
+    let mut vl = init(&b);
+    vl.set_xtalk_data(&x).unwrap();
+    assert_eq!(uploaded(&b, vl), sent);
+}
+
+#[test]
+fn calibration_arguments_are_checked() {
+    for ((prc, samples, mm), e) in [
+        ((0, 4, 600), ConfigError::XtalkReflectanceOutOfRange{ prc: 0 }),
+        ((100, 4, 600), ConfigError::XtalkReflectanceOutOfRange{ prc: 100 }),
+        ((3, 0, 600), ConfigError::XtalkSamplesOutOfRange{ n: 0 }),
+        ((3, 17, 600), ConfigError::XtalkSamplesOutOfRange{ n: 17 }),
+        ((3, 4, 599), ConfigError::XtalkDistanceOutOfRange{ mm: 599 }),
+        ((3, 4, 3001), ConfigError::XtalkDistanceOutOfRange{ mm: 3001 }),
+    ] {
+        let sim = Sim::flat(1000);
+        let mut vl = init(&sim);
+        let n = sim.accesses();
+
+        assert_eq!(vl.calibrate_xtalk(PrcU8(prc), samples, mm).err(), Some(Error::Config(e)));
+        assert_eq!(sim.accesses(), n, "nothing sent to the sensor");
+    }
+}
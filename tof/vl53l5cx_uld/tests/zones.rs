@@ -0,0 +1,51 @@
+/*
+* Host side tests of the per-zone view ('ResultsData::zones()').
+*
+*   $ cargo test --target x86_64-unknown-linux-gnu --features distance_mm --test zones
+*/
+use vl53l5cx_uld::{ResultsData, TargetStatus};
+
+fn res() -> ResultsData<4> {
+    let mut r = ResultsData::<4>::empty();
+    for row in 0..4 {
+        for col in 0..4 {
+            r.distance_mm[0][row][col] = (100*row + col) as u16;
+            r.target_status[0][row][col] = if col == 3 { TargetStatus::SigmaTooHigh } else { TargetStatus::Valid };
+        }
+    }
+    r
+}
+
+#[test]
+fn zones_row_by_row() {
+    let r = res();
+    let v: Vec<(usize,usize)> = r.zones().map(|z| (z.row, z.col)).collect();
+
+    assert_eq!(v.len(), 16);
+    assert_eq!(v[0], (0,0));
+    assert_eq!(v[1], (0,1));
+    assert_eq!(v[4], (1,0));
+    assert_eq!(v[15], (3,3));
+}
+
+#[test]
+fn target_fields_in_step() {
+    let r = res();
+
+    for z in r.zones() {
+        for t in z.targets {
+            assert_eq!(t.distance_mm, (100*z.row + z.col) as u16);
+            assert_eq!(t.is_valid(), z.col != 3);
+        }
+    }
+}
+
+#[test]
+fn valid_targets_only() {
+    let r = res();
+    let v: Vec<_> = r.valid_targets().collect();
+
+    assert_eq!(v.len(), 12);
+    assert!(v.iter().all(|(_,col,_)| *col != 3));
+    assert_eq!(v[4].2.distance_mm, 101);    // (1,1)
+}
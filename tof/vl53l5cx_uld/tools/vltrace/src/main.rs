@@ -0,0 +1,115 @@
+/*
+* Decode a 'RecordingPlatform' trace (format version 1) into text, one record per line:
+*   <<
+*       {offset}  {RD|WR|WR_H|DELAY|ADDR}[!]  {details}
+*   <<
+*
+* '!' marks a failed I2C call. Reads and short writes show (up to) 'SHOW' first bytes of data.
+*
+* Usage:
+*   $ vltrace {trace file}
+*   $ vltrace - < {trace file}
+*/
+use std::{
+    env,
+    fs,
+    io::{self, Read},
+    process::ExitCode,
+};
+
+const SHOW: usize = 16;
+
+fn main() -> ExitCode {
+    let fname = match env::args().nth(1) {
+        Some(x) => x,
+        None => {
+            eprintln!("Usage: vltrace {{trace file|-}}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let t = if fname == "-" {
+        let mut v = Vec::new();
+        io::stdin().read_to_end(&mut v).map(|_| v)
+    } else {
+        fs::read(&fname)
+    };
+
+    let t = match t {
+        Ok(x) => x,
+        Err(e) => {
+            eprintln!("Cannot read '{}': {}", fname, e);
+            return ExitCode::from(2);
+        }
+    };
+
+    match decode(&t) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn decode(t: &[u8]) -> Result<(), String> {
+    if t.len() < 5 || &t[..4] != b"VLTR" {
+        return Err("Not a trace (no 'VLTR' header)".into());
+    }
+    if t[4] != 1 {
+        return Err(format!("Unsupported trace version: {}", t[4]));
+    }
+
+    let get = |at: usize, n: usize| t.get(at..at+n).ok_or_else(|| format!("Truncated record at {}", at));
+    let u16_at = |at: usize| get(at, 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+
+    let mut at = 5;
+    let (mut n_rd, mut n_wr) = (0_usize, 0_usize);
+
+    while at < t.len() {
+        let (tag, failed) = (t[at] & 0x7f, t[at] & 0x80 != 0);
+        let mark = if failed { "!" } else { " " };
+
+        let len = match tag {
+            0x01 | 0x02 | 0x03 => {
+                let (index, n) = (u16_at(at+1)?, u16_at(at+3)? as usize);
+                let (name, pl) = match tag {
+                    0x01 => ("RD", if failed { 0 } else { n }),
+                    0x02 => ("WR", n),
+                    _ => ("WR_H", 4)
+                };
+                let data = get(at+5, pl)?;
+
+                let details = if tag == 0x03 {
+                    format!("fnv1a={:#010x}", u32::from_le_bytes([data[0], data[1], data[2], data[3]]))
+                } else {
+                    hex(data)
+                };
+                println!("{:>7}  {:<5}{} {:#06x} {:>5}  {}", at, name, mark, index, n, details);
+
+                if tag == 0x01 { n_rd += 1 } else { n_wr += 1 };
+                5 + pl
+            },
+            0x04 => {
+                let b = get(at+1, 4)?;
+                println!("{:>7}  DELAY   {} ms", at, u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+                5
+            },
+            0x05 => {
+                println!("{:>7}  ADDR    {:#04x}", at, get(at+1, 1)?[0]);
+                2
+            },
+            x => return Err(format!("Unknown tag {:#04x} at {}", x, at))
+        };
+        at += len;
+    }
+
+    eprintln!("{} bytes; {} reads, {} writes", t.len(), n_rd, n_wr);
+    Ok(())
+}
+
+fn hex(bs: &[u8]) -> String {
+    let mut s: String = bs.iter().take(SHOW).map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ");
+    if bs.len() > SHOW { s.push_str(" .."); }
+    s
+}
@@ -8,6 +8,9 @@
 #pragma once
 #include "vl53l5cx_api.h"
 #include "vl53l5cx_buffers.h"
+#include "vl53l5cx_plugin_motion_indicator.h"
+#include "vl53l5cx_plugin_detection_thresholds.h"
+#include "vl53l5cx_plugin_xtalk.h"
 
 // We don't do standard headers, so... (from '/usr/include/clang/18/include/__stddef_size_t.h'):
 typedef __SIZE_TYPE__ size_t;
@@ -48,6 +51,38 @@ enum PowerMode {
 };
     // Using 'CamelCase' since Rust prefers that for enums.
 
+// Detection thresholds plugin
+//
+enum ThresholdMeasurement {
+    DISTANCE_MM = VL53L5CX_DISTANCE_MM,                     // 1 (u8)
+    SIGNAL_PER_SPAD_KCPS = VL53L5CX_SIGNAL_PER_SPAD_KCPS,   // 2 (u8)
+    RANGE_SIGMA_MM = VL53L5CX_RANGE_SIGMA_MM,               // 4 (u8)
+    AMBIENT_PER_SPAD_KCPS = VL53L5CX_AMBIENT_PER_SPAD_KCPS, // 8 (u8)
+    NB_TARGET_DETECTED = VL53L5CX_NB_TARGET_DETECTED,       // 9 (u8)
+    TARGET_STATUS = VL53L5CX_TARGET_STATUS,                 // 12 (u8)
+    NB_SPADS_ENABLED = VL53L5CX_NB_SPADS_ENABLED,           // 13 (u8)
+    MOTION_INDICATOR = VL53L5CX_MOTION_INDICATOR            // 19 (u8)
+};
+enum ThresholdType {
+    IN_WINDOW = VL53L5CX_IN_WINDOW,                         // 0 (u8)
+    OUT_OF_WINDOW = VL53L5CX_OUT_OF_WINDOW,                 // 1 (u8)
+    LESS_THAN_EQUAL_MIN = VL53L5CX_LESS_THAN_EQUAL_MIN_CHECKER, // 2 (u8)
+    GREATER_THAN_MAX = VL53L5CX_GREATER_THAN_MAX_CHECKER,   // 3 (u8)
+    EQUAL_MIN = VL53L5CX_EQUAL_MIN_CHECKER,                 // 4 (u8)
+    NOT_EQUAL_MIN = VL53L5CX_NOT_EQUAL_MIN_CHECKER          // 5 (u8)
+};
+enum ThresholdOperation {
+    OR = VL53L5CX_OPERATION_OR,     // 0 (u8); same as 'VL53L5CX_OPERATION_NONE'
+    AND = VL53L5CX_OPERATION_AND    // 2 (u8)
+};
+const uint8_t NB_THRESHOLDS = VL53L5CX_NB_THRESHOLDS;     // 64
+const uint8_t LAST_THRESHOLD = VL53L5CX_LAST_THRESHOLD;   // 128 (flag, or'ed to '.zone_num')
+
+// Calibration data (offsets from the NVM; xtalk)
+//
+const uint16_t OFFSET_BUFFER_SIZE = VL53L5CX_OFFSET_BUFFER_SIZE;   // 488
+const uint16_t XTALK_BUFFER_SIZE = VL53L5CX_XTALK_BUFFER_SIZE;   // 776
+
 /// @brief Status of operations.
 ///
 ///     Note that official documentation only mentions these cases:
@@ -59,19 +94,20 @@ enum PowerMode {
 ///         |255|major error (usually timeout in I2C)|
 ///         |other|"combination of multiple errors"|
 ///
-///     This means listing anything else in the API would not really make sense.
+///     The vendor header does define a few more (below). We decode them, but anything can come out of the
+///     "combination".
 ///
 ///     Note: Also the app side code ('RdMulti', 'MsWait' etc.) affects the codes.
 ///
 const uint8_t ST_OK = VL53L5CX_STATUS_OK;                       // 0
 const uint8_t ST_ERROR = VL53L5CX_STATUS_ERROR;	                // |255
-    // not passed
-    //const uint8_t ST_TIMEOUT_ERROR = VL53L5CX_STATUS_TIMEOUT_ERROR;     // |1
-    //const uint8_t CORRUPTED_FRAME = VL53L5CX_STATUS_CORRUPTED_FRAME;    // |2
-    //const uint8_t CRC_CSUM_FAILED = VL53L5CX_STATUS_CRC_CSUM_FAILED;	// |3
-    //const uint8_t XTALK_FAILED = VL53L5CX_STATUS_XTALK_FAILED;          // |4
-    //const uint8_t MCU_ERROR = VL53L5CX_MCU_ERROR;                       // |66 (0x42)
-    //const uint8_t INVALID_PARAM = VL53L5CX_STATUS_INVALID_PARAM;    // |127 (0x7f)
+const uint8_t ST_TIMEOUT_ERROR = VL53L5CX_STATUS_TIMEOUT_ERROR;     // |1
+const uint8_t ST_CORRUPTED_FRAME = VL53L5CX_STATUS_CORRUPTED_FRAME; // |2
+const uint8_t ST_CRC_CSUM_FAILED = VL53L5CX_STATUS_CRC_CSUM_FAILED;	// |3
+const uint8_t ST_XTALK_FAILED = VL53L5CX_STATUS_XTALK_FAILED;       // |4
+const uint8_t ST_MCU_ERROR = VL53L5CX_MCU_ERROR;                    // |66 (0x42)
+const uint8_t ST_INVALID_PARAM = VL53L5CX_STATUS_INVALID_PARAM;     // |127 (0x7f)
+    // Decoded in Rust 'Error::from_uld()'.
 
 /* tbd. do we need this?
 const size_t MAX_RESULTS_SIZE = VL53L5CX_MAX_RESULTS_SIZE;